@@ -10,7 +10,7 @@ struct GreeterScope {}
 impl GreeterScope {
     /// Cmdr command to greet someone.
     /// Takes one parameter and prints a greeting
-    #[cmd(greet)]
+    #[cmd(greet, usage = "<name>")]
     fn greet_method(&self, args: &[String]) -> CommandResult {
         println!("Hello {}", args[0]);
         Ok(Action::Done)
@@ -14,12 +14,12 @@ impl OverrideScope {
     }
 
     /// All the help, all the time
-    fn help(&self, _args: &[String]) -> CommandResult {
-        println!("Help Stuff");
+    fn help_with(&self, interaction: &mut Interaction, _args: &[String]) -> CommandResult {
+        interaction.write_line("Help Stuff");
         for command in self.commands().all_commands() {
-            println!("- {}", command.name());
+            interaction.write_line(&format!("- {}", command.name()));
             if let Some(help_text) = &command.help_text() {
-                println!("{}", help_text)
+                interaction.write_paragraph(help_text)
             }
         }
 
@@ -3,6 +3,8 @@
 //! A sub scope is similar to a CommandResult::NewScope but switches back to the calling scope when
 //! it finishes by returning CommandResult::Exit. CommandResult::Quit still quits the whole
 //! application.
+//! Declaring sub scopes with sub_scopes lets the functions in `cmdr::docs` include them in the
+//! generated reference documentation, the docs command prints it as Markdown.
 
 use cmdr::*;
 
@@ -14,11 +16,22 @@ impl MainScope {
         "main scope>".to_string()
     }
 
+    fn sub_scopes(&self) -> Vec<(String, ScopeDescription)> {
+        vec![("sub".to_string(), SubScope { count: 1 }.commands())]
+    }
+
     #[cmd]
     /// Switch to the second scope
     fn sub(&mut self, _args: &[String]) -> CommandResult {
         Action::sub_scope(SubScope { count: 1 })
     }
+
+    #[cmd]
+    /// Print the reference documentation of all scopes as Markdown
+    fn docs(&mut self, writer: &mut dyn LineWriter, _args: &[String]) -> CommandResult {
+        writer.write(&docs::markdown("sub-scope", self));
+        Ok(Action::Done)
+    }
 }
 
 /// The subscope. Has a quit and an exit command to quit the application or exit the sub scope and
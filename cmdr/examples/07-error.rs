@@ -1,7 +1,8 @@
-//! Return errors from commands, right now we only have the fatal error that exits the application
-//! it allows you to return an error code that can be returned from main()
-//! CommandResult will probably be re-factored in version 0.4.0 to be a normal Result<> Type that
-//! can either be a CommandResult or a CommandError
+//! Return errors from commands. The fatal error exits the application, it allows you to return an
+//! error code that can be returned from main(). Application errors are wrapped in Error::Custom,
+//! they are shown to the user with their causes and the application goes on. Call `custom()`
+//! from `ResultExt` on results with errors that `?` can not convert.
+//! cmd_main runs the command loop and exits the process with the error code.
 
 use cmdr::*;
 
@@ -18,12 +19,25 @@ impl MainScope {
     fn error(&mut self, _args: &[String]) -> CommandResult {
         Err(Error::Fatal(101))
     }
+
+    #[cmd(usage = "<number>")]
+    /// Parse a number, shows an error when the argument is not a number
+    fn parse(&mut self, args: &[String]) -> CommandResult {
+        let number: i64 = args.first().ok_or("Missing argument")?.parse()?;
+        println!("Parsed {}", number);
+        Ok(Action::Done)
+    }
+
+    #[cmd(usage = "<name>")]
+    /// Show an environment variable, shows an error when it is not set
+    fn env(&mut self, args: &[String]) -> CommandResult {
+        let name = args.first().ok_or("Missing argument")?;
+        let value = std::env::var(name).custom()?;
+        println!("{}={}", name, value);
+        Ok(Action::Done)
+    }
 }
 
 fn main() {
-    std::process::exit(match cmd_loop(&mut MainScope {}) {
-        Ok(_) => 0,
-        Err(Error::Fatal(error_code)) => error_code,
-        _ => -1, // This should not happen.
-    })
+    cmd_main(&mut MainScope {})
 }
@@ -1,34 +1,161 @@
 //! Shows how to replace all strings that are output by cmdr with translated versions. This example
 //! provides Dutch translations for all messages built into cmdr. And changes the help command to
-//! German.
+//! German. The runner passes the messages to the pager as well.
 
+use cmdr::line_reader::RustyLineReader;
+use cmdr::line_writer::{PagerWriter, PrintlnWriter};
 use cmdr::*;
 
-struct TranslatedScope {}
+/// Dutch translations for all messages built into cmdr
+#[derive(Debug)]
+struct DutchMessages;
 
-#[cmdr(help_command = "?")]
-impl TranslatedScope {
-    /// Handle errors, output a translated error string for all known errors
-    fn handle_error(&mut self, error: Error) -> CommandResult {
+impl Messages for DutchMessages {
+    fn error(&self, error: &Error) -> String {
         match error {
-            Error::InvalidCommand(command) => {
-                println!("Onbekend commando: {}", command);
-                Ok(Action::Done)
-            }
+            Error::InvalidCommand(command) => format!("Onbekend commando: {}", command),
             Error::InvalidNumberOfArguments(command) => {
-                println!("Verkeerd aantal argumenten voor commando: {}", command);
-                Ok(Action::Done)
+                format!("Verkeerd aantal argumenten voor commando: {}", command)
             }
             Error::NoHelpForCommand(command) => {
-                println!("Geen hulp beschikbaar voor commando: {}", command);
-                Ok(Action::Done)
+                format!("Geen hulp beschikbaar voor commando: {}", command)
             }
-            _ => Err(error),
+            Error::Cancelled => "Afgebroken".to_string(),
+            error => error.to_string(),
+        }
+    }
+
+    fn application_error(&self, message: &str) -> String {
+        format!("Fout: {}", message)
+    }
+
+    fn caused_by(&self, message: &str) -> String {
+        format!("Veroorzaakt door: {}", message)
+    }
+
+    fn help_header(&self) -> String {
+        "Dit zijn de geldige commando's in deze scope:".to_string()
+    }
+
+    fn global_help_header(&self) -> String {
+        "Deze commando's zijn in elke scope beschikbaar:".to_string()
+    }
+
+    fn mount_help_header(&self, name: &str) -> String {
+        format!("Commando's van {}:", name)
+    }
+
+    fn usage(&self, command: &str, usage: &str) -> String {
+        format!("Gebruik: {} {}", command, usage)
+    }
+
+    fn confirm_prompt(&self, question: &str) -> String {
+        format!("{} [j/N]", question)
+    }
+
+    fn confirm_answer(&self, answer: &str) -> Option<bool> {
+        match answer.trim().to_lowercase().as_ref() {
+            "j" | "ja" => Some(true),
+            "" | "n" | "nee" => Some(false),
+            _ => None,
+        }
+    }
+
+    fn confirm_invalid(&self) -> String {
+        "Antwoord j of n".to_string()
+    }
+
+    fn select_prompt(&self, prompt: &str, options: usize) -> String {
+        format!("{} [1-{}]", prompt, options)
+    }
+
+    fn select_empty(&self) -> String {
+        "Er is niets om uit te kiezen".to_string()
+    }
+
+    fn select_invalid(&self, options: usize) -> String {
+        format!("Kies een nummer van 1 tot en met {}", options)
+    }
+
+    fn pager_more(&self) -> String {
+        "--Meer--".to_string()
+    }
+
+    fn pager_skipping(&self) -> String {
+        "...overslaan".to_string()
+    }
+
+    fn pager_not_found(&self) -> String {
+        "Patroon niet gevonden".to_string()
+    }
+
+    fn too_many_connections(&self) -> String {
+        "Te veel verbindingen, probeer het later opnieuw".to_string()
+    }
+
+    fn jobs_help_header(&self) -> String {
+        "Deze commando's beheren achtergrondtaken:".to_string()
+    }
+
+    fn jobs_help(&self) -> String {
+        "Toon de achtergrondtaken die draaien".to_string()
+    }
+
+    fn wait_help(&self) -> String {
+        "Wacht tot een achtergrondtaak, of alle taken, klaar is".to_string()
+    }
+
+    fn kill_help(&self) -> String {
+        "Vraag een achtergrondtaak om te stoppen".to_string()
+    }
+
+    fn no_jobs(&self) -> String {
+        "Er draaien geen taken".to_string()
+    }
+
+    fn no_such_job(&self, job: &str) -> String {
+        format!("Taak bestaat niet: {}", job)
+    }
+
+    fn job_done(&self, id: usize, name: &str) -> String {
+        format!("[{}] Klaar      {}", id, name)
+    }
+
+    fn job_killed(&self, id: usize, name: &str) -> String {
+        format!("[{}] Gestopt    {}", id, name)
+    }
+
+    fn job_failed(&self, id: usize, name: &str, error: &str) -> String {
+        format!("[{}] Mislukt    {}: {}", id, name, error)
+    }
+}
+
+struct TranslatedScope {}
+
+#[cmdr(help_command = "?")]
+impl TranslatedScope {
+    /// Verwijder alle bestanden, vraagt eerst om bevestiging
+    #[cmd]
+    fn verwijder(&mut self, interaction: &mut Interaction, _args: &[String]) -> CommandResult {
+        if interaction.confirm("Weet je het zeker?")? {
+            interaction.write_line("Alles is verwijderd");
+        }
+        Ok(Action::Done)
+    }
+
+    /// Toon een lange lijst, de pager vraagt om meer
+    #[cmd]
+    fn lijst(&mut self, interaction: &mut Interaction, _args: &[String]) -> CommandResult {
+        for line in 1..=200 {
+            interaction.write_line(&format!("Regel {}", line));
         }
+        Ok(Action::Done)
     }
 }
 
 fn main() -> cmdr::Result<()> {
-    cmd_loop(&mut TranslatedScope {})?;
+    Runner::new(RustyLineReader::new(), PagerWriter::new(PrintlnWriter {}))
+        .with_messages(DutchMessages)
+        .run(&mut TranslatedScope {})?;
     Ok(())
 }
@@ -0,0 +1,43 @@
+//! Commands can write structured records to the LineWriter instead of formatting output by hand.
+//! Records are rendered as aligned tables for humans, start this example with --json to write
+//! them as JSON lines for scripts instead.
+
+use cmdr::line_reader::RustyLineReader;
+use cmdr::line_writer::PrintlnWriter;
+use cmdr::*;
+
+struct InventoryScope {}
+
+#[cmdr]
+impl InventoryScope {
+    /// Show a single item
+    #[cmd]
+    fn show(&mut self, writer: &mut dyn LineWriter, args: &[String]) -> CommandResult {
+        writer.write_record(
+            &Record::new()
+                .field("name", args.first().cloned())
+                .field("count", 3)
+                .field("in_stock", true),
+        );
+        Ok(Action::Done)
+    }
+
+    /// List all items
+    #[cmd]
+    fn list(&mut self, writer: &mut dyn LineWriter, _args: &[String]) -> CommandResult {
+        writer.write_records(&[
+            Record::new().field("name", "hammer").field("count", 3),
+            Record::new()
+                .field("name", "screwdriver")
+                .field("count", 12),
+        ]);
+        Ok(Action::Done)
+    }
+}
+
+fn main() -> cmdr::Result<()> {
+    Runner::new(RustyLineReader::new(), PrintlnWriter {})
+        .with_json_output(std::env::args().any(|arg| arg == "--json"))
+        .run(&mut InventoryScope {})?;
+    Ok(())
+}
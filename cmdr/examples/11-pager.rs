@@ -0,0 +1,27 @@
+//! Long output can be paged by wrapping the LineWriter in a PagerWriter. Output that does not fit
+//! on the terminal is shown one page at a time, press space for the next page, enter for the next
+//! line, / to search and q to stop.
+
+use cmdr::line_reader::RustyLineReader;
+use cmdr::line_writer::{PagerWriter, PrintlnWriter};
+use cmdr::*;
+
+struct ReportScope {}
+
+#[cmdr]
+impl ReportScope {
+    /// Print a long report
+    #[cmd]
+    fn report(&mut self, writer: &mut dyn LineWriter, _args: &[String]) -> CommandResult {
+        for line in 1..=500 {
+            writer.write_line(&format!("Report line {}", line));
+        }
+        Ok(Action::Done)
+    }
+}
+
+fn main() -> cmdr::Result<()> {
+    Runner::new(RustyLineReader::new(), PagerWriter::new(PrintlnWriter {}))
+        .run(&mut ReportScope {})?;
+    Ok(())
+}
@@ -0,0 +1,62 @@
+//! Commands can ask the user questions by taking an Interaction parameter. The interaction reads
+//! answers from the same LineReader as the commands, so this also works with scripted input.
+
+use cmdr::*;
+
+struct FileScope {
+    files: Vec<String>,
+}
+
+#[cmdr]
+impl FileScope {
+    /// Delete a file after asking for confirmation
+    #[cmd(usage = "<file>")]
+    fn delete(&mut self, interaction: &mut Interaction, args: &[String]) -> CommandResult {
+        let file = args
+            .first()
+            .ok_or_else(|| Error::InvalidNumberOfArguments("delete".to_string()))?;
+
+        if interaction.confirm(&format!("Delete {}?", file))? {
+            self.files.retain(|existing| existing != file);
+            interaction.write_styled_line(Style::Success, "Deleted");
+        }
+        Ok(Action::Done)
+    }
+
+    /// Pick a file from a list
+    #[cmd]
+    fn pick(&mut self, interaction: &mut Interaction, _args: &[String]) -> CommandResult {
+        let options: Vec<&str> = self.files.iter().map(|file| file.as_ref()).collect();
+        let picked = interaction.select("Which file?", &options)?;
+
+        interaction.write_line(&format!("You picked {}", options[picked]));
+        Ok(Action::Done)
+    }
+
+    /// Log in with a user name and password
+    #[cmd]
+    fn login(&mut self, interaction: &mut Interaction, _args: &[String]) -> CommandResult {
+        let user = interaction.input_valid("User:", |user| {
+            if user.is_empty() {
+                Err("User can not be empty".to_string())
+            } else {
+                Ok(())
+            }
+        })?;
+        let password = interaction.password("Password:")?;
+
+        interaction.write_line(&format!(
+            "Logged in as {} with a {} character password",
+            user,
+            password.len()
+        ));
+        Ok(Action::Done)
+    }
+}
+
+fn main() -> cmdr::Result<()> {
+    cmd_loop(&mut FileScope {
+        files: vec!["notes.txt".to_string(), "todo.txt".to_string()],
+    })?;
+    Ok(())
+}
@@ -0,0 +1,41 @@
+//! Commands can be async functions when the async feature is enabled. They are awaited by an
+//! AsyncRunner, pressing Ctrl-C while a command runs stops it.
+//!
+//! Run with `cargo run --example 13-async --features async`
+
+use cmdr::*;
+use std::time::Duration;
+
+struct TimerScope {}
+
+#[cmdr]
+impl TimerScope {
+    /// Wait for a number of seconds without blocking
+    #[cmd(usage = "<seconds>")]
+    async fn sleep(&mut self, writer: &mut dyn LineWriter, args: &[String]) -> CommandResult {
+        let seconds: u64 = args
+            .first()
+            .and_then(|seconds| seconds.parse().ok())
+            .ok_or_else(|| Error::InvalidNumberOfArguments("sleep".to_string()))?;
+
+        // Stands in for a database query or http request
+        tokio::task::spawn_blocking(move || std::thread::sleep(Duration::from_secs(seconds)))
+            .await
+            .map_err(|_| Error::Fatal(1))?;
+        writer.write_styled_line(Style::Success, "Done sleeping");
+        Ok(Action::Done)
+    }
+
+    /// Commands that are not async can be used in the same scope
+    #[cmd]
+    fn greet(&mut self, writer: &mut dyn LineWriter, args: &[String]) -> CommandResult {
+        writer.write_line(&format!("Hello {}", args.join(" ")));
+        Ok(Action::Done)
+    }
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> cmdr::Result<()> {
+    cmd_loop_async(&mut TimerScope {}).await?;
+    Ok(())
+}
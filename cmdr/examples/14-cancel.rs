@@ -0,0 +1,29 @@
+//! Long running commands can check the cancel token of their Interaction. Pressing Ctrl-C while
+//! a command runs cancels it and returns to the prompt, Ctrl-C at an empty prompt quits.
+
+use cmdr::*;
+use std::thread::sleep;
+use std::time::Duration;
+
+struct CountScope {}
+
+#[cmdr]
+impl CountScope {
+    /// Count slowly until Ctrl-C is pressed
+    #[cmd]
+    fn count(&mut self, interaction: &mut Interaction, _args: &[String]) -> CommandResult {
+        for number in 1.. {
+            interaction.check_cancelled()?;
+
+            interaction.write_line(&number.to_string());
+            interaction.flush();
+            sleep(Duration::from_millis(200));
+        }
+        Ok(Action::Done)
+    }
+}
+
+fn main() -> cmdr::Result<()> {
+    cmd_loop(&mut CountScope {})?;
+    Ok(())
+}
@@ -0,0 +1,35 @@
+//! Commands can start work in the background by returning Action::background. The built-in jobs,
+//! wait and kill commands manage running jobs once the first job was started, a notice is shown
+//! when a job is done.
+
+use cmdr::*;
+use std::thread::sleep;
+use std::time::Duration;
+
+struct DownloadScope {}
+
+#[cmdr]
+impl DownloadScope {
+    /// Pretend to download a file in the background, takes a second per megabyte
+    #[cmd(usage = "<file> <megabytes>")]
+    fn download(&mut self, args: &[String]) -> CommandResult {
+        if args.len() != 2 {
+            return Err(Error::InvalidNumberOfArguments("download".to_string()));
+        }
+        let file = args[0].clone();
+        let size: u64 = args[1].parse().unwrap_or(1);
+
+        Action::background(&format!("download {}", file), move |cancel| {
+            for _ in 0..size * 10 {
+                cancel.check()?;
+                sleep(Duration::from_millis(100));
+            }
+            Ok(format!("Downloaded {} megabytes to {}", size, file))
+        })
+    }
+}
+
+fn main() -> cmdr::Result<()> {
+    cmd_loop(&mut DownloadScope {})?;
+    Ok(())
+}
@@ -0,0 +1,44 @@
+//! Serve a scope to remote users over TCP. Connect with `nc localhost 9000` and press enter on the
+//! console to shut the server down. Start with `--framed` to connect with
+//! `cmdr-client localhost:9000` instead.
+
+use cmdr::server::Server;
+use cmdr::*;
+
+struct GreeterScope {
+    greeting: String,
+}
+
+#[cmdr]
+impl GreeterScope {
+    /// Greet someone
+    #[cmd(usage = "<name>")]
+    fn greet(&mut self, writer: &mut dyn LineWriter, args: &[String]) -> CommandResult {
+        writer.write_line(&format!("{} {}", self.greeting, args.join(" ")));
+        Ok(Action::Done)
+    }
+
+    /// Close the connection
+    #[cmd]
+    fn quit(&mut self, _args: &[String]) -> CommandResult {
+        Ok(Action::Quit)
+    }
+}
+
+fn main() -> std::io::Result<()> {
+    let server = Server::new(|| GreeterScope {
+        greeting: "Hello".to_string(),
+    })
+    .with_max_connections(4)
+    .with_framed_protocol(std::env::args().any(|arg| arg == "--framed"))
+    .listen_tcp("127.0.0.1:9000")?;
+
+    println!(
+        "Listening on {:?}, press enter to stop",
+        server.local_addr()
+    );
+    std::io::stdin().read_line(&mut String::new())?;
+
+    server.shutdown();
+    Ok(())
+}
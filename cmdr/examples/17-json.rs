@@ -0,0 +1,26 @@
+//! Other programs can drive a scope with JSON requests, one per line. Try:
+//!
+//! `echo '{"command": "greet", "args": ["Bob"]}' | cargo run --example 17-json --features json`
+
+use cmdr::line_reader::FileLineReader;
+use cmdr::line_writer::PrintlnWriter;
+use cmdr::*;
+
+struct GreeterScope {}
+
+#[cmdr]
+impl GreeterScope {
+    /// Greet someone
+    #[cmd(usage = "<name>")]
+    fn greet(&mut self, writer: &mut dyn LineWriter, args: &[String]) -> CommandResult {
+        writer.write_line(&format!("Hello {}", args.join(" ")));
+        writer.write_record(&Record::new().field("greeted", args.join(" ")));
+        Ok(Action::Done)
+    }
+}
+
+fn main() -> cmdr::Result<()> {
+    JsonRunner::new(FileLineReader::new(std::io::stdin()), PrintlnWriter {})
+        .run(&mut GreeterScope {})?;
+    Ok(())
+}
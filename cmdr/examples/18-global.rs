@@ -0,0 +1,58 @@
+//! Commands of a global scope are available in every scope the runner runs, so commands like quit
+//! and clear only have to be written once. Help lists them in a separate section.
+
+use cmdr::line_reader::RustyLineReader;
+use cmdr::line_writer::PrintlnWriter;
+use cmdr::*;
+
+/// Commands that are available everywhere
+struct GlobalCommands {}
+
+#[cmdr(no_help)]
+impl GlobalCommands {
+    /// Quit the application
+    #[cmd]
+    fn quit(&mut self, _args: &[String]) -> CommandResult {
+        Ok(Action::Quit)
+    }
+
+    /// Clear the screen
+    #[cmd]
+    fn clear(&mut self, writer: &mut dyn LineWriter, _args: &[String]) -> CommandResult {
+        writer.write("\x1b[2J\x1b[H");
+        Ok(Action::Done)
+    }
+}
+
+struct MainScope {}
+
+#[cmdr]
+impl MainScope {
+    /// Open a sub scope, quit and clear still work there
+    #[cmd]
+    fn sub(&mut self, _args: &[String]) -> CommandResult {
+        Action::sub_scope(SubScope {})
+    }
+}
+
+struct SubScope {}
+
+#[cmdr]
+impl SubScope {
+    fn prompt(&self) -> String {
+        "sub>".to_string()
+    }
+
+    /// Go back to the main scope
+    #[cmd]
+    fn exit(&mut self, _args: &[String]) -> CommandResult {
+        Ok(Action::Exit)
+    }
+}
+
+fn main() -> cmdr::Result<()> {
+    Runner::new(RustyLineReader::new(), PrintlnWriter {})
+        .with_global_scope(GlobalCommands {})
+        .run(&mut MainScope {})?;
+    Ok(())
+}
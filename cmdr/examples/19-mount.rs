@@ -0,0 +1,66 @@
+//! Build a scope from reusable pieces by mounting scopes that are stored in fields. Commands of a
+//! mounted scope are run by prefixing them with the name it is mounted as, like `db query`.
+//! `mount(db)` mounts the field db as db, `mount(files = "fs")` mounts the field fs as files.
+//!
+//! Only the commands of a mounted scope are used, its hooks and `handle_error` are not called.
+//! Async commands of a mounted scope are awaited when the scope that mounts it has async commands
+//! and runs on an `AsyncRunner`, otherwise they fail with `Error::AsyncCommand`.
+
+use cmdr::*;
+
+/// Database commands
+struct DbCommands {
+    queries: u32,
+}
+
+#[cmdr(no_help)]
+impl DbCommands {
+    /// Run a query on the database
+    #[cmd(usage = "<query>")]
+    fn query(&mut self, args: &[String]) -> CommandResult {
+        self.queries += 1;
+        println!("Query {}: {}", self.queries, args.join(" "));
+        Ok(Action::Done)
+    }
+}
+
+/// File system commands
+struct FsCommands {}
+
+#[cmdr(no_help)]
+impl FsCommands {
+    /// List the files in a directory
+    #[cmd(alias(dir), usage = "[path]")]
+    fn ls(&mut self, args: &[String]) -> CommandResult {
+        let path = args.first().map_or(".", String::as_str);
+        for entry in std::fs::read_dir(path).map_err(Error::custom)? {
+            println!(
+                "{}",
+                entry.map_err(Error::custom)?.file_name().to_string_lossy()
+            );
+        }
+        Ok(Action::Done)
+    }
+}
+
+struct AppScope {
+    db: DbCommands,
+    fs: FsCommands,
+}
+
+#[cmdr(mount(db, files = "fs"))]
+impl AppScope {
+    /// Quit the application
+    #[cmd]
+    fn quit(&mut self, _args: &[String]) -> CommandResult {
+        Ok(Action::Quit)
+    }
+}
+
+fn main() -> cmdr::Result<()> {
+    cmd_loop(&mut AppScope {
+        db: DbCommands { queries: 0 },
+        fs: FsCommands {},
+    })?;
+    Ok(())
+}
@@ -0,0 +1,53 @@
+//! Commands of a DynamicScope are closures registered at runtime, here they are made from a
+//! configuration instead of being written as methods. Commands can also be removed again.
+
+use cmdr::*;
+
+/// Configuration with a command name and the text it prints on every line
+const CONFIG: &str = "hello = Hello world
+motd = Welcome to the dynamic example";
+
+fn main() -> cmdr::Result<()> {
+    let mut scope = DynamicScope::new()
+        .with_prompt("dyn>")
+        .with_help("Commands in this scope are read from a configuration");
+
+    for line in CONFIG.lines() {
+        let mut parts = line.splitn(2, '=');
+        let name = parts.next().unwrap_or_default().trim().to_string();
+        let text = parts.next().unwrap_or_default().trim().to_string();
+
+        scope.add_command(
+            ScopeCmdDescription::new(name, vec![], Some(format!("Print \"{}\"", text))),
+            move |_, interaction| {
+                interaction.write_line(&text);
+                Ok(Action::Done)
+            },
+        );
+    }
+
+    let mut count = 0;
+    scope.add_command(
+        ScopeCmdDescription::new(
+            "count".to_string(),
+            vec!["c".to_string()],
+            Some("Count how many times this command was called".to_string()),
+        ),
+        move |_, interaction| {
+            count += 1;
+            interaction.write_line(&count.to_string());
+            Ok(Action::Done)
+        },
+    );
+
+    scope.add_command(
+        ScopeCmdDescription::new("quit".to_string(), vec![], Some("Quit".to_string())),
+        |_, _| Ok(Action::Quit),
+    );
+
+    // The motd command was only needed for this demo, remove it again
+    scope.remove_command("motd");
+
+    cmd_loop(&mut scope)?;
+    Ok(())
+}
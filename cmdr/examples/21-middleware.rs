@@ -0,0 +1,88 @@
+//! Middleware runs around every command of every scope. This example times commands and blocks
+//! commands that change things while the application is read only.
+
+use cmdr::line_reader::RustyLineReader;
+use cmdr::line_writer::PrintlnWriter;
+use cmdr::*;
+use std::time::Instant;
+
+/// Shows how long every command took
+#[derive(Debug)]
+struct Timing;
+
+impl Middleware for Timing {
+    fn handle<'a>(
+        &'a mut self,
+        _line: &'a Line,
+        command: &'a ScopeCmdDescription,
+        mut next: Next<'a>,
+    ) -> LocalBoxFuture<'a, CommandResult> {
+        Box::pin(async move {
+            let start = Instant::now();
+            let result = next.run().await;
+            next.writer()
+                .write_line(&format!("{} took {:?}", command.name(), start.elapsed()));
+            result
+        })
+    }
+}
+
+/// Stops commands that are not in the list of allowed commands
+#[derive(Debug)]
+struct ReadOnly {
+    allowed: Vec<&'static str>,
+}
+
+impl Middleware for ReadOnly {
+    fn handle<'a>(
+        &'a mut self,
+        _line: &'a Line,
+        command: &'a ScopeCmdDescription,
+        mut next: Next<'a>,
+    ) -> LocalBoxFuture<'a, CommandResult> {
+        Box::pin(async move {
+            if self.allowed.contains(&command.name()) {
+                next.run().await
+            } else {
+                next.writer()
+                    .write_line(&format!("{} is not allowed, read only", command.name()));
+                Ok(Action::Done)
+            }
+        })
+    }
+}
+
+struct FileScope {}
+
+#[cmdr]
+impl FileScope {
+    /// List files
+    #[cmd]
+    fn list(&mut self, writer: &mut dyn LineWriter, _args: &[String]) -> CommandResult {
+        writer.write_line("a.txt b.txt");
+        Ok(Action::Done)
+    }
+
+    /// Delete a file
+    #[cmd]
+    fn delete(&mut self, writer: &mut dyn LineWriter, args: &[String]) -> CommandResult {
+        writer.write_line(&format!("Deleted {}", args.join(" ")));
+        Ok(Action::Done)
+    }
+
+    /// Quit the application
+    #[cmd]
+    fn quit(&mut self, _args: &[String]) -> CommandResult {
+        Ok(Action::Quit)
+    }
+}
+
+fn main() -> cmdr::Result<()> {
+    Runner::new(RustyLineReader::new(), PrintlnWriter {})
+        .with_middleware(Timing)
+        .with_middleware(ReadOnly {
+            allowed: vec!["help", "list", "quit"],
+        })
+        .run(&mut FileScope {})?;
+    Ok(())
+}
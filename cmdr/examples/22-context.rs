@@ -0,0 +1,94 @@
+//! The context of a runner is shared by all scopes it runs. Commands borrow it with a
+//! `&mut Context<T>` argument, so sub scopes do not need a copy of the state.
+
+use cmdr::line_reader::RustyLineReader;
+use cmdr::line_writer::PrintlnWriter;
+use cmdr::*;
+use std::collections::HashMap;
+
+/// State of the application, shared by all scopes
+#[derive(Debug, Default)]
+struct Database {
+    values: HashMap<String, String>,
+}
+
+struct MainScope {}
+
+#[cmdr]
+impl MainScope {
+    /// Set a value
+    #[cmd(usage = "<key> <value>")]
+    fn set(&mut self, context: &mut Context<Database>, args: &[String]) -> CommandResult {
+        if args.len() != 2 {
+            return Err(Error::InvalidNumberOfArguments("set".to_string()));
+        }
+
+        context.values.insert(args[0].clone(), args[1].clone());
+        Ok(Action::Done)
+    }
+
+    /// Open a scope to read values
+    #[cmd]
+    fn read(&mut self, _args: &[String]) -> CommandResult {
+        Action::sub_scope(ReadScope {})
+    }
+
+    /// Quit the application
+    #[cmd]
+    fn quit(&mut self, _args: &[String]) -> CommandResult {
+        Ok(Action::Quit)
+    }
+}
+
+struct ReadScope {}
+
+#[cmdr]
+impl ReadScope {
+    fn prompt(&self) -> String {
+        "read>".to_string()
+    }
+
+    /// Show a value that was set in the main scope
+    #[cmd(usage = "<key>")]
+    fn get(
+        &mut self,
+        context: &mut Context<Database>,
+        writer: &mut dyn LineWriter,
+        args: &[String],
+    ) -> CommandResult {
+        for key in args {
+            match context.values.get(key) {
+                Some(value) => writer.write_line(value),
+                None => writer.write_line(&format!("{} is not set", key)),
+            }
+        }
+        Ok(Action::Done)
+    }
+
+    /// Remove all values after asking for confirmation
+    #[cmd]
+    fn clear(
+        &mut self,
+        interaction: &mut Interaction,
+        context: &mut Context<Database>,
+        _args: &[String],
+    ) -> CommandResult {
+        if interaction.confirm("Remove all values?")? {
+            context.values.clear();
+        }
+        Ok(Action::Done)
+    }
+
+    /// Go back to the main scope
+    #[cmd]
+    fn exit(&mut self, _args: &[String]) -> CommandResult {
+        Ok(Action::Exit)
+    }
+}
+
+fn main() -> cmdr::Result<()> {
+    Runner::new(RustyLineReader::new(), PrintlnWriter {})
+        .with_context(Database::default())
+        .run(&mut MainScope {})?;
+    Ok(())
+}
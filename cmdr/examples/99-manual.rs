@@ -47,11 +47,11 @@ impl Scope for GreeterScope {
         &mut self,
         command: &ScopeCmdDescription,
         args: &[String],
-        writer: &mut dyn LineWriter,
+        interaction: &mut Interaction,
     ) -> CommandResult {
         match command.name() {
-            "help" => self.help(args),
-            "greet" => self.greet(writer, args),
+            "help" => self.help_with(interaction, args),
+            "greet" => self.greet(interaction, args),
             "quit" => self.quit(),
             _ => Err(Error::InvalidCommand(command.name().to_string())),
         }
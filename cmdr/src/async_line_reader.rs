@@ -0,0 +1,137 @@
+//! Contains the AsyncLineReader trait and implementations to read lines without blocking the
+//! async runtime
+
+use crate::future::LocalBoxFuture;
+use crate::line_reader::LineReader;
+use crate::{Error, ScopeDescription};
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::task::spawn_blocking;
+
+/// A line reader that reads lines asynchronously, used by the `AsyncRunner`
+pub trait AsyncLineReader {
+    /// Returns a future that resolves to the next line that is entered
+    fn read_line<'a>(&'a mut self, prompt: &'a str) -> LocalBoxFuture<'a, Result<String, Error>>;
+
+    /// Called with the commands of the active scope before a line is read so the reader can use
+    /// them for highlighting and hints. The default implementation ignores them
+    fn set_commands(&mut self, _commands: &ScopeDescription) {}
+
+    /// Blocking line reader used to answer questions asked through an `Interaction` while a
+    /// command runs. The default implementation returns None, questions then fail with
+    /// `Error::LineReaderError`
+    fn line_reader(&mut self) -> Option<&mut dyn LineReader> {
+        None
+    }
+}
+
+/// Wraps a blocking LineReader, like the `RustyLineReader`, and reads lines on a separate thread
+/// so other tasks keep running while the user is typing
+#[derive(Debug)]
+pub struct BlockingLineReader<R: LineReader + Send + 'static> {
+    wrapped: Option<R>,
+}
+
+impl<R: LineReader + Send + 'static> BlockingLineReader<R> {
+    /// Construct and return a new `BlockingLineReader`
+    pub fn new(wrapped: R) -> Self {
+        BlockingLineReader {
+            wrapped: Some(wrapped),
+        }
+    }
+}
+
+impl<R: LineReader + Send + 'static> AsyncLineReader for BlockingLineReader<R> {
+    fn read_line<'a>(&'a mut self, prompt: &'a str) -> LocalBoxFuture<'a, Result<String, Error>> {
+        Box::pin(async move {
+            // The reader is moved to the blocking thread and put back when the line is read
+            let mut reader = self.wrapped.take().ok_or(Error::LineReaderError)?;
+            let prompt = prompt.to_string();
+
+            let (reader, line) = spawn_blocking(move || {
+                let line = reader.read_line(&prompt);
+                (reader, line)
+            })
+            .await
+            .map_err(|_| Error::LineReaderError)?;
+
+            self.wrapped = Some(reader);
+            line
+        })
+    }
+
+    fn set_commands(&mut self, commands: &ScopeDescription) {
+        if let Some(reader) = self.wrapped.as_mut() {
+            reader.set_commands(commands)
+        }
+    }
+
+    fn line_reader(&mut self) -> Option<&mut dyn LineReader> {
+        match self.wrapped.as_mut() {
+            Some(reader) => Some(reader),
+            None => None,
+        }
+    }
+}
+
+/// Read commands from an async io stream like stdin or a socket
+#[derive(Debug)]
+pub struct TokioLineReader<R: AsyncRead + Unpin> {
+    reader: BufReader<R>,
+}
+
+impl<R: AsyncRead + Unpin> TokioLineReader<R> {
+    /// Create a new TokioLineReader
+    pub fn new(reader: R) -> Self {
+        TokioLineReader {
+            reader: BufReader::new(reader),
+        }
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncLineReader for TokioLineReader<R> {
+    fn read_line<'a>(&'a mut self, _: &'a str) -> LocalBoxFuture<'a, Result<String, Error>> {
+        Box::pin(async move {
+            let mut line = String::new();
+            match self.reader.read_line(&mut line).await {
+                Ok(0) => Err(Error::CtrlD),
+                Ok(_) => Ok(line),
+                Err(error) => Err(error.into()),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::line_reader::FileLineReader;
+
+    fn block_on<T>(future: impl std::future::Future<Output = T>) -> T {
+        tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap()
+            .block_on(future)
+    }
+
+    #[test]
+    fn tokio_line_reader_should_read_lines_until_end() {
+        let mut reader = TokioLineReader::new(&b"one\ntwo\n"[..]);
+
+        block_on(async {
+            assert_eq!(reader.read_line(">").await, Ok("one\n".to_string()));
+            assert_eq!(reader.read_line(">").await, Ok("two\n".to_string()));
+            assert_eq!(reader.read_line(">").await, Err(Error::CtrlD));
+        })
+    }
+
+    #[test]
+    fn blocking_line_reader_should_keep_reader_between_lines() {
+        let mut reader = BlockingLineReader::new(FileLineReader::new(&b"one\ntwo\n"[..]));
+
+        block_on(async {
+            assert_eq!(reader.read_line(">").await, Ok("one\n".to_string()));
+            assert!(reader.line_reader().is_some());
+            assert_eq!(reader.read_line(">").await, Ok("two\n".to_string()));
+        })
+    }
+}
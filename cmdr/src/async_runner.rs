@@ -0,0 +1,164 @@
+use crate::async_line_reader::AsyncLineReader;
+use crate::future::LocalBoxFuture;
+use crate::interaction::Interaction;
+use crate::line_reader::{LineReader, NoLineReader};
+use crate::messages::Messages;
+use crate::middleware::Middleware;
+use crate::run_loop::{BlockingInput, Input, RunLoop};
+use crate::scope::Scope;
+use crate::{line_writer::LineWriter, CommandResult, Error};
+use crate::{ScopeCmdDescription, ScopeDescription};
+use std::any::Any;
+use std::sync::Arc;
+
+/// Runs scopes on an async runtime. Reads lines from an AsyncLineReader and awaits async commands,
+/// scopes without async commands are run as well. Pressing Ctrl-C while an async command runs
+/// drops the command and cancels its token.
+#[derive(Debug)]
+pub struct AsyncRunner<R: AsyncLineReader, W: LineWriter> {
+    core: RunLoop<AsyncInput<R>, W>,
+}
+
+impl<R: AsyncLineReader, W: LineWriter> AsyncRunner<R, W> {
+    /// Create a new runner that takes lines from the `reader` and executes them using the `scope`
+    pub fn new(reader: R, writer: W) -> Self {
+        AsyncRunner {
+            core: RunLoop::new(
+                AsyncInput {
+                    reader,
+                    no_reader: NoLineReader,
+                },
+                writer,
+            ),
+        }
+    }
+
+    /// Set whether Ctrl-C at an empty prompt quits, when false a new prompt is shown instead.
+    /// Ctrl-C while a command runs always cancels the command
+    pub fn with_quit_on_ctrl_c(mut self, quit: bool) -> Self {
+        self.core.quit_on_ctrl_c = quit;
+        self
+    }
+
+    /// Set whether records that commands write are written as JSON lines for scripts instead of
+    /// tables for humans, other output is not changed
+    pub fn with_json_output(mut self, json: bool) -> Self {
+        self.core.json_output = json;
+        self
+    }
+
+    /// Show messages instead of the default English texts built into cmdr
+    pub fn with_messages<M: Messages + 'static>(mut self, messages: M) -> Self {
+        self.core.set_messages(Arc::new(messages));
+        self
+    }
+
+    /// Set the context that commands in all scopes can borrow with a `&mut Context<T>` argument
+    pub fn with_context<T: Any>(mut self, context: T) -> Self {
+        self.core.set_context(context);
+        self
+    }
+
+    /// Set a scope with commands that are available in every scope. Commands of the running scope
+    /// are used first, global commands are used when the running scope does not have the command
+    pub fn with_global_scope<S: Scope + 'static>(mut self, scope: S) -> Self {
+        self.core.set_global_scope(Box::new(scope));
+        self
+    }
+
+    /// Add middleware that runs around every command of the running scope and the global scope,
+    /// async commands included. Middleware runs in the order it was added
+    pub fn with_middleware<M: Middleware + 'static>(mut self, middleware: M) -> Self {
+        self.core.middleware.push(Box::new(middleware));
+        self
+    }
+
+    /// Start reading lines and executing them
+    pub async fn run<S: Scope>(&mut self, scope: &mut S) -> CommandResult {
+        self.core.run(scope).await
+    }
+
+    /// Run like `run` from main() and return the exit code for the process. Returns 0 when the
+    /// loop finishes normally, fatal errors return their error code and other errors are printed
+    /// to stderr and return 1
+    pub async fn run_main<S: Scope>(&mut self, scope: &mut S) -> i32 {
+        self.run_main_with(scope, Error::exit_code).await
+    }
+
+    /// Like `run_main` but `exit_code` chooses the exit code for errors
+    pub async fn run_main_with<S, F>(&mut self, scope: &mut S, exit_code: F) -> i32
+    where
+        S: Scope,
+        F: FnOnce(&Error) -> i32,
+    {
+        self.core.run_main(scope, exit_code).await
+    }
+}
+
+/// Reads lines from an AsyncLineReader and awaits async commands
+#[derive(Debug)]
+struct AsyncInput<R: AsyncLineReader> {
+    reader: R,
+    /// Answers questions when the reader has no blocking line reader
+    no_reader: NoLineReader,
+}
+
+impl<R: AsyncLineReader> Input for AsyncInput<R> {
+    fn read_line<'a>(&'a mut self, prompt: &'a str) -> LocalBoxFuture<'a, Result<String, Error>> {
+        self.reader.read_line(prompt)
+    }
+
+    fn set_commands(&mut self, commands: &ScopeDescription) {
+        self.reader.set_commands(commands)
+    }
+
+    fn line_reader(&mut self) -> &mut dyn LineReader {
+        match self.reader.line_reader() {
+            Some(reader) => reader,
+            None => &mut self.no_reader,
+        }
+    }
+
+    // The async feature needs the Rust version that tokio needs, which is newer than the MSRV
+    #[allow(clippy::incompatible_msrv)]
+    fn run_command<'a>(
+        scope: &'a mut dyn Scope,
+        command: &'a ScopeCmdDescription,
+        args: &'a [String],
+        interaction: &'a mut Interaction<'_>,
+        catch_interrupt: bool,
+    ) -> LocalBoxFuture<'a, CommandResult> {
+        Box::pin(async move {
+            let cancel = interaction.cancel_token().clone();
+
+            match scope.as_async() {
+                Some(async_scope) => {
+                    let mut running = async_scope.run_command_async(command, args, interaction);
+                    if !catch_interrupt {
+                        return running.await;
+                    }
+
+                    tokio::select! {
+                        result = &mut running => result,
+                        Ok(()) = tokio::signal::ctrl_c() => {
+                            // Work that was handed off to other tasks or threads can check the token
+                            cancel.cancel();
+                            Err(Error::Cancelled)
+                        }
+                    }
+                }
+                // Sync commands are run like the Runner runs them
+                None => {
+                    BlockingInput::<NoLineReader>::run_command(
+                        scope,
+                        command,
+                        args,
+                        interaction,
+                        catch_interrupt,
+                    )
+                    .await
+                }
+            }
+        })
+    }
+}
@@ -0,0 +1,49 @@
+//! Contains the AsyncScope trait for scopes with commands that are async functions
+use crate::description::ScopeCmdDescription;
+use crate::future::LocalBoxFuture;
+use crate::interaction::Interaction;
+use crate::result::CommandResult;
+use crate::scope::{run_mounted, Scope};
+use crate::Line;
+
+/// Trait for scopes that have async commands. The cmdr macro implements this trait for you when an
+/// impl block contains `async fn` commands. Async scopes can only be run by an `AsyncRunner`.
+pub trait AsyncScope: Scope {
+    /// Run an entered command and return a future that resolves to the result
+    fn run_command_async<'a>(
+        &'a mut self,
+        command: &'a ScopeCmdDescription,
+        args: &'a [String],
+        interaction: &'a mut Interaction<'_>,
+    ) -> LocalBoxFuture<'a, CommandResult>;
+}
+
+/// Run a command of a scope that is mounted as name like `run_mounted` does, async commands of
+/// the mounted scope are awaited. The cmdr macro uses this for mounts in scopes that have async
+/// commands themselves
+pub fn run_mounted_async<'a>(
+    scope: &'a mut dyn Scope,
+    name: &'a str,
+    args: &'a [String],
+    interaction: &'a mut Interaction<'_>,
+) -> LocalBoxFuture<'a, CommandResult> {
+    Box::pin(async move {
+        if let Some((command, args)) = args.split_first() {
+            let commands = scope.commands();
+            let line = Line {
+                command: command.clone(),
+                args: args.to_vec(),
+            };
+
+            if let (Some(command), Some(async_scope)) =
+                (commands.command_for_line(&line), scope.as_async())
+            {
+                return async_scope
+                    .run_command_async(command, args, interaction)
+                    .await;
+            }
+        }
+
+        run_mounted(scope, name, args, interaction)
+    })
+}
@@ -0,0 +1,42 @@
+//! Client for cmdr servers that use the framed protocol. Connects to a TCP address or, with a
+//! `unix:` prefix, to a Unix domain socket.
+//!
+//! ```text
+//! cmdr-client localhost:9000
+//! cmdr-client unix:/tmp/app.sock
+//! ```
+
+use cmdr::client::Client;
+use cmdr::line_reader::RustyLineReader;
+use cmdr::line_writer::PrintlnWriter;
+use std::io;
+use std::process::exit;
+
+fn run(address: &str) -> io::Result<()> {
+    let mut reader = RustyLineReader::new();
+    let mut writer = PrintlnWriter {};
+
+    #[cfg(unix)]
+    {
+        if let Some(path) = address.strip_prefix("unix:") {
+            return Client::connect_unix(path)?.run(&mut reader, &mut writer);
+        }
+    }
+
+    Client::connect_tcp(address)?.run(&mut reader, &mut writer)
+}
+
+fn main() {
+    let address = match std::env::args().nth(1) {
+        Some(address) => address,
+        None => {
+            eprintln!("Usage: cmdr-client <host:port> | unix:<path>");
+            exit(2)
+        }
+    };
+
+    if let Err(error) = run(&address) {
+        eprintln!("cmdr-client: {}", error);
+        exit(1)
+    }
+}
@@ -0,0 +1,111 @@
+//! Cancellation tokens that let long running commands stop when the user presses Ctrl-C
+use crate::result::{Error, Result};
+use crate::terminal::{self, CatchInterrupt};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// Lets a long running command find out if it should stop. The token that is passed to a command
+/// through its Interaction is cancelled when the user presses Ctrl-C while the command runs.
+/// Tokens can be cloned and passed to other threads, all clones are cancelled together.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken {
+    state: Arc<TokenState>,
+}
+
+#[derive(Debug, Default)]
+struct TokenState {
+    cancelled: AtomicBool,
+    /// The start of the CatchInterrupt while Ctrl-C cancels the token
+    interrupt_start: Mutex<Option<usize>>,
+}
+
+impl TokenState {
+    fn set_interrupt_start(&self, start: Option<usize>) {
+        *self
+            .interrupt_start
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = start;
+    }
+
+    fn interrupted(&self) -> bool {
+        self.interrupt_start
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .map_or(false, terminal::interrupted_since)
+    }
+}
+
+impl CancelToken {
+    /// Construct a token that is only cancelled by calling cancel
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancel the token and all its clones
+    pub fn cancel(&self) {
+        self.state.cancelled.store(true, Ordering::SeqCst)
+    }
+
+    /// Check if the token was cancelled
+    pub fn is_cancelled(&self) -> bool {
+        self.state.cancelled.load(Ordering::SeqCst) || self.state.interrupted()
+    }
+
+    /// Return `Error::Cancelled` when the token was cancelled, so commands can stop with `?`
+    pub fn check(&self) -> Result<()> {
+        if self.is_cancelled() {
+            Err(Error::Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Cancel this token when Ctrl-C is pressed until the returned guard is dropped
+    pub(crate) fn cancel_on_interrupt(&self) -> CancelOnInterrupt {
+        let catch = CatchInterrupt::new();
+        self.state.set_interrupt_start(Some(catch.start()));
+
+        CancelOnInterrupt {
+            token: self.clone(),
+            catch,
+        }
+    }
+}
+
+/// Cancels a token when Ctrl-C is pressed while it exists
+#[derive(Debug)]
+pub(crate) struct CancelOnInterrupt {
+    token: CancelToken,
+    catch: CatchInterrupt,
+}
+
+impl Drop for CancelOnInterrupt {
+    fn drop(&mut self) {
+        // Remember the interrupt, clones of the token can outlive the command
+        if self.catch.interrupted() {
+            self.token.cancel();
+        }
+        self.token.state.set_interrupt_start(None);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_token_should_not_be_cancelled() {
+        assert_eq!(CancelToken::new().check(), Ok(()));
+    }
+
+    #[test]
+    fn cancel_should_cancel_all_clones() {
+        let token = CancelToken::new();
+        let clone = token.clone();
+
+        token.cancel();
+
+        assert!(clone.is_cancelled());
+        assert_eq!(clone.check(), Err(Error::Cancelled));
+    }
+}
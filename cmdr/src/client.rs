@@ -0,0 +1,173 @@
+//! A client for cmdr servers that use the framed protocol, this is what the `cmdr-client` binary
+//! runs. Lines are edited locally so the user gets line editing and history.
+use crate::description::ScopeDescription;
+use crate::line_reader::LineReader;
+use crate::line_writer::LineWriter;
+use crate::protocol::Frame;
+use crate::result::Result as CmdrResult;
+use std::io::{self, BufReader, ErrorKind, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
+#[cfg(unix)]
+use std::path::Path;
+
+/// Connection to a cmdr server that was started with the framed protocol turned on
+#[derive(Debug)]
+pub struct Client<R: Read, W: Write> {
+    input: BufReader<R>,
+    output: W,
+}
+
+impl Client<TcpStream, TcpStream> {
+    /// Connect to a server that listens on a TCP address
+    pub fn connect_tcp<A: ToSocketAddrs>(address: A) -> io::Result<Self> {
+        let stream = TcpStream::connect(address)?;
+        Ok(Client::new(stream.try_clone()?, stream))
+    }
+}
+
+#[cfg(unix)]
+impl Client<UnixStream, UnixStream> {
+    /// Connect to a server that listens on a Unix domain socket
+    pub fn connect_unix<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let stream = UnixStream::connect(path)?;
+        Ok(Client::new(stream.try_clone()?, stream))
+    }
+}
+
+impl<R: Read, W: Write> Client<R, W> {
+    /// Create a client that reads frames from input and sends frames to output
+    pub fn new(input: R, output: W) -> Self {
+        Client {
+            input: BufReader::new(input),
+            output,
+        }
+    }
+
+    /// Run the session until the server ends it or the user presses Ctrl-D. Lines are read from
+    /// reader when the server asks for them, output of the server is written to writer
+    pub fn run(
+        &mut self,
+        reader: &mut dyn LineReader,
+        writer: &mut dyn LineWriter,
+    ) -> io::Result<()> {
+        loop {
+            let answered = match Frame::read_from(&mut self.input)? {
+                Some(Frame::Output(output)) => {
+                    writer.write(&output);
+                    writer.flush();
+                    true
+                }
+                Some(Frame::Prompt(prompt)) => {
+                    // Hints and completion use the commands of the scope the server is in
+                    reader.set_commands(&self.describe()?);
+                    self.answer(reader.read_line(&prompt))?
+                }
+                Some(Frame::Password(prompt)) => self.answer(reader.read_password(&prompt))?,
+                Some(Frame::End) | None => false,
+                // Frames that are only sent by clients, or only in answer to requests
+                Some(_) => true,
+            };
+
+            if !answered {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Ask the server for the commands of its current scope, can only be called while the server
+    /// waits for a line
+    pub fn describe(&mut self) -> io::Result<ScopeDescription> {
+        self.request(Frame::Describe, |frame| match frame {
+            Frame::Description(description) => Some(description),
+            _ => None,
+        })
+    }
+
+    /// Ask the server how the command name at the end of line can be completed, can only be
+    /// called while the server waits for a line
+    pub fn complete(&mut self, line: &str) -> io::Result<Vec<String>> {
+        self.request(Frame::Complete(line.to_string()), |frame| match frame {
+            Frame::Completions(completions) => Some(completions),
+            _ => None,
+        })
+    }
+
+    /// Send a request and wait for the frame answer picks out
+    fn request<T, F>(&mut self, request: Frame, answer: F) -> io::Result<T>
+    where
+        F: Fn(Frame) -> Option<T>,
+    {
+        request.write_to(&mut self.output)?;
+
+        loop {
+            match Frame::read_from(&mut self.input)? {
+                Some(frame) => {
+                    if let Some(answer) = answer(frame) {
+                        return Ok(answer);
+                    }
+                }
+                None => return Err(io::Error::new(ErrorKind::UnexpectedEof, "Server closed")),
+            }
+        }
+    }
+
+    /// Send a line to the server, ends the session when no line was read. Returns false when the
+    /// session has ended
+    fn answer(&mut self, line: CmdrResult<String>) -> io::Result<bool> {
+        match line {
+            Ok(line) => {
+                Frame::Line(line).write_to(&mut self.output)?;
+                Ok(true)
+            }
+            Err(_) => {
+                Frame::End.write_to(&mut self.output)?;
+                Ok(false)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::line_reader::FileLineReader;
+
+    fn frames(frames: &[Frame]) -> Vec<u8> {
+        let mut stream = Vec::new();
+        for frame in frames {
+            frame.write_to(&mut stream).unwrap();
+        }
+        stream
+    }
+
+    #[test]
+    fn client_should_answer_prompts_and_show_output() {
+        let description = ScopeDescription::new(None, vec![]);
+        let server_frames = frames(&[
+            Frame::Prompt(">".to_string()),
+            Frame::Description(description.clone()),
+            Frame::Output("Hello Bob\n".to_string()),
+            Frame::Prompt(">".to_string()),
+            Frame::Description(description),
+        ]);
+        let mut sent = Vec::new();
+        let mut output = String::new();
+
+        Client::new(&server_frames[..], &mut sent)
+            .run(&mut FileLineReader::new(&b"greet Bob\n"[..]), &mut output)
+            .unwrap();
+
+        assert_eq!(output, "Hello Bob\n");
+        assert_eq!(
+            sent,
+            frames(&[
+                Frame::Describe,
+                Frame::Line("greet Bob\n".to_string()),
+                Frame::Describe,
+                Frame::End
+            ])
+        );
+    }
+}
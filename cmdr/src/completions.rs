@@ -0,0 +1,519 @@
+//! Generate shell completion scripts for programs that run a single command from their command
+//! line arguments, for example `mytool greet Bob`.
+//!
+//! The scripts complete command names and aliases as the first argument and the flags mentioned
+//! in the usage line of a command, like `--loud` in `[--loud] <name>`, after that. Commands of
+//! mounted scopes are completed after the name of the mount, like `mytool db query`.
+use crate::description::{ScopeCmdDescription, ScopeDescription};
+
+/// Generate a bash completion script, load it with `source` or install it in the
+/// bash-completion directory
+pub fn bash(program: &str, description: &ScopeDescription) -> String {
+    let function = function_name(program);
+
+    format!(
+        "{}() {{\n    local cur=\"${{COMP_WORDS[COMP_CWORD]}}\"\n    local word\n\n{}}}\n\n\
+         complete -F {} {}\n",
+        function,
+        bash_scope(description, 1, 4),
+        function,
+        shell_quote(program)
+    )
+}
+
+/// Complete the commands of a scope as word depth and their flags after it, the commands of
+/// mounted scopes are completed after the name of the mount
+fn bash_scope(description: &ScopeDescription, depth: usize, indent: usize) -> String {
+    let pad = " ".repeat(indent);
+    let mut script = format!(
+        "{0}if [ \"$COMP_CWORD\" -eq {1} ]; then\n{2}{0}    return\n{0}fi\n",
+        pad,
+        depth,
+        bash_words(&command_names(description), indent + 4)
+    );
+
+    let cases: Vec<String> = with_flags(description)
+        .map(|command| {
+            format!(
+                "{}    {})\n{}{}        ;;\n",
+                pad,
+                case_pattern(command),
+                bash_words(&flags(command), indent + 8),
+                pad
+            )
+        })
+        .chain(description.mounts().map(|(name, mount)| {
+            format!(
+                "{}    {})\n{}{}        ;;\n",
+                pad,
+                shell_quote(name),
+                bash_scope(mount, depth + 1, indent + 8),
+                pad
+            )
+        }))
+        .collect();
+
+    if !cases.is_empty() {
+        script.push_str(&format!(
+            "\n{}case \"${{COMP_WORDS[{}]}}\" in\n{}{}esac\n",
+            pad,
+            depth,
+            cases.concat(),
+            pad
+        ));
+    }
+    script
+}
+
+/// Add the words that start with what is typed to the completions. The words are not passed to
+/// compgen, it would expand them
+fn bash_words(words: &[&str], indent: usize) -> String {
+    let pad = " ".repeat(indent);
+    let words: Vec<String> = words.iter().map(|word| shell_quote(word)).collect();
+
+    format!(
+        "{0}for word in {1}; do\n{0}    [[ \"$word\" == \"$cur\"* ]] && COMPREPLY+=(\"$word\")\n\
+         {0}done\n",
+        pad,
+        words.join(" ")
+    )
+}
+
+/// Generate a zsh completion script, install it as `_program` in a directory in `fpath` or load
+/// it with `source`. Programs with names that zsh can not read from the `#compdef` line have to
+/// be loaded with `source`
+pub fn zsh(program: &str, description: &ScopeDescription) -> String {
+    let function = function_name(program);
+    let tag = if is_plain(program) {
+        format!("#compdef {}\n\n", program)
+    } else {
+        String::new()
+    };
+
+    format!(
+        "{0}{1}() {{\n{2}}}\n\nif [ \"$funcstack[1]\" = \"{1}\" ]; then\n    {1} \"$@\"\n\
+         else\n    compdef {1} {3}\nfi\n",
+        tag,
+        function,
+        zsh_scope(description, 1, 4),
+        shell_quote(program)
+    )
+}
+
+/// Complete the commands of a scope as word depth, like `bash_scope`
+fn zsh_scope(description: &ScopeDescription, depth: usize, indent: usize) -> String {
+    let pad = " ".repeat(indent);
+    let mut script = format!(
+        "{0}if (( CURRENT == {1} )); then\n{0}    local -a commands\n{0}    commands=(\n",
+        pad,
+        depth + 1
+    );
+
+    for command in description.all_commands() {
+        for name in names(command) {
+            script.push_str(&format!(
+                "{}        {}\n",
+                pad,
+                shell_quote(&format!(
+                    "{}:{}",
+                    name.replace('\\', "\\\\").replace(':', "\\:"),
+                    command.summary()
+                ))
+            ));
+        }
+    }
+
+    script.push_str(&format!(
+        "{0}    )\n\n{0}    _describe 'command' commands\n{0}    return\n{0}fi\n",
+        pad
+    ));
+
+    let cases: Vec<String> = with_flags(description)
+        .map(|command| {
+            format!(
+                "{0}    {1})\n{0}        compadd -- {2}\n{0}        ;;\n",
+                pad,
+                case_pattern(command),
+                flags(command).join(" ")
+            )
+        })
+        .chain(description.mounts().map(|(name, mount)| {
+            format!(
+                "{0}    {1})\n{2}{0}        ;;\n",
+                pad,
+                shell_quote(name),
+                zsh_scope(mount, depth + 1, indent + 8)
+            )
+        }))
+        .collect();
+
+    if !cases.is_empty() {
+        script.push_str(&format!(
+            "\n{0}case $words[{1}] in\n{2}{0}esac\n",
+            pad,
+            depth + 1,
+            cases.concat()
+        ));
+    }
+    script
+}
+
+/// Generate a fish completion script, install it in `~/.config/fish/completions` or load it
+/// with `source`
+pub fn fish(program: &str, description: &ScopeDescription) -> String {
+    let program = fish_quote(program);
+    let mut script = format!("complete -c {} -f\n", program);
+    fish_scope(&program, description, &[], &mut script);
+    script
+}
+
+/// Complete the commands of a scope that is mounted as path, the root scope has an empty path
+fn fish_scope(program: &str, description: &ScopeDescription, path: &[&str], script: &mut String) {
+    // Conditions for the words typed after the program name
+    let tokens = "(commandline -opc)";
+    let mounted: Vec<String> = path
+        .iter()
+        .enumerate()
+        .map(|(index, name)| format!("; and test {}[{}] = {}", tokens, index + 2, fish_word(name)))
+        .collect();
+    let at_command = if path.is_empty() {
+        "__fish_use_subcommand".to_string()
+    } else {
+        format!(
+            "test (count {}) -eq {}{}",
+            tokens,
+            path.len() + 1,
+            mounted.concat()
+        )
+    };
+
+    for command in description.all_commands() {
+        for name in names(command) {
+            script.push_str(&format!(
+                "complete -c {} -n {} -a {}",
+                program,
+                fish_word(&at_command),
+                fish_quote(name)
+            ));
+            if !command.summary().is_empty() {
+                script.push_str(&format!(" -d {}", fish_quote(command.summary())));
+            }
+            script.push('\n');
+        }
+    }
+
+    for command in with_flags(description) {
+        let names: Vec<String> = names(command).into_iter().map(fish_word).collect();
+        let condition = if path.is_empty() {
+            format!("__fish_seen_subcommand_from {}", names.join(" "))
+        } else {
+            format!(
+                "test (count {0}) -gt {1}{2}; and contains -- {0}[{3}] {4}",
+                tokens,
+                path.len() + 1,
+                mounted.concat(),
+                path.len() + 2,
+                names.join(" ")
+            )
+        };
+
+        for flag in flags(command) {
+            let option = match flag.strip_prefix("--") {
+                Some(long) => format!("-l {}", fish_quote(long)),
+                None if flag.len() == 2 => format!("-s {}", fish_quote(&flag[1..])),
+                // Old style options like -name
+                None => format!("-o {}", fish_quote(&flag[1..])),
+            };
+            script.push_str(&format!(
+                "complete -c {} -n {} {}\n",
+                program,
+                fish_quote(&condition),
+                option
+            ));
+        }
+    }
+
+    for (name, mount) in description.mounts() {
+        let mut mount_path = path.to_vec();
+        mount_path.push(name);
+        fish_scope(program, mount, &mount_path, script);
+    }
+}
+
+/// The name and aliases of a command
+fn names(command: &ScopeCmdDescription) -> Vec<&str> {
+    Some(command.name())
+        .into_iter()
+        .chain(command.aliases().map(|alias| alias.as_str()))
+        .collect()
+}
+
+/// The names and aliases of all commands in a scope
+fn command_names(description: &ScopeDescription) -> Vec<&str> {
+    description.all_commands().flat_map(names).collect()
+}
+
+/// Commands that have flags in their usage line
+fn with_flags(description: &ScopeDescription) -> impl Iterator<Item = &ScopeCmdDescription> {
+    description
+        .all_commands()
+        .filter(|command| !flags(command).is_empty())
+}
+
+/// Flags mentioned in the usage line of a command, `[-v|--verbose] [--name=<name>]` has the
+/// flags `-v`, `--verbose` and `--name`
+fn flags(command: &ScopeCmdDescription) -> Vec<&str> {
+    command
+        .usage()
+        .unwrap_or("")
+        .split(|c: char| c.is_whitespace() || "[]()|".contains(c))
+        .map(|word| word.split('=').next().unwrap_or(""))
+        .filter(|word| {
+            word.starts_with('-')
+                && !word.trim_start_matches('-').is_empty()
+                && word
+                    .chars()
+                    .all(|c| c == '-' || c == '_' || c.is_ascii_alphanumeric())
+        })
+        .collect()
+}
+
+/// Name of the shell function that completes a program
+fn function_name(program: &str) -> String {
+    let name: String = program
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    format!("_{}", name)
+}
+
+/// The names of a command as a case pattern that matches them literally
+fn case_pattern(command: &ScopeCmdDescription) -> String {
+    names(command)
+        .into_iter()
+        .map(shell_quote)
+        .collect::<Vec<_>>()
+        .join("|")
+}
+
+/// Check if text can be used in a script without quotes
+fn is_plain(text: &str) -> bool {
+    !text.is_empty()
+        && text
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_.+".contains(c))
+}
+
+/// Quote text for bash and zsh
+fn shell_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "'\\''"))
+}
+
+/// Quote text for fish
+fn fish_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+/// Quote text for fish when it is not a plain word
+fn fish_word(text: &str) -> String {
+    if is_plain(text) {
+        text.to_string()
+    } else {
+        fish_quote(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_test_scope() -> ScopeDescription {
+        ScopeDescription::new(
+            None,
+            vec![
+                ScopeCmdDescription::new(
+                    "greet".to_string(),
+                    vec!["hi".to_string()],
+                    Some("Greet someone's friend\nMore help".to_string()),
+                )
+                .with_usage("[-l|--loud] [--times=<n>] <name>"),
+                ScopeCmdDescription::new("quit".to_string(), vec![], None),
+            ],
+        )
+        .with_mount(
+            "db",
+            ScopeDescription::new(
+                Some("Database".to_string()),
+                vec![ScopeCmdDescription::new(
+                    "query".to_string(),
+                    vec![],
+                    Some("Run a query".to_string()),
+                )
+                .with_usage("[-name] <sql>")],
+            ),
+        )
+    }
+
+    /// A scope with names that mean something to shells
+    fn get_special_scope() -> ScopeDescription {
+        ScopeDescription::new(
+            None,
+            vec![
+                ScopeCmdDescription::new("$(rm)".to_string(), vec!["a|b".to_string()], None)
+                    .with_usage("[--force]"),
+            ],
+        )
+    }
+
+    #[test]
+    fn should_find_flags_in_usage() {
+        let scope = get_test_scope();
+        let greet = scope.all_commands().next().unwrap();
+
+        assert_eq!(flags(greet), vec!["-l", "--loud", "--times"]);
+    }
+
+    #[test]
+    fn should_generate_bash_completion() {
+        assert_eq!(
+            bash("my-tool", &get_test_scope()),
+            r#"_my_tool() {
+    local cur="${COMP_WORDS[COMP_CWORD]}"
+    local word
+
+    if [ "$COMP_CWORD" -eq 1 ]; then
+        for word in 'greet' 'hi' 'quit' 'db'; do
+            [[ "$word" == "$cur"* ]] && COMPREPLY+=("$word")
+        done
+        return
+    fi
+
+    case "${COMP_WORDS[1]}" in
+        'greet'|'hi')
+            for word in '-l' '--loud' '--times'; do
+                [[ "$word" == "$cur"* ]] && COMPREPLY+=("$word")
+            done
+            ;;
+        'db')
+            if [ "$COMP_CWORD" -eq 2 ]; then
+                for word in 'query'; do
+                    [[ "$word" == "$cur"* ]] && COMPREPLY+=("$word")
+                done
+                return
+            fi
+
+            case "${COMP_WORDS[2]}" in
+                'query')
+                    for word in '-name'; do
+                        [[ "$word" == "$cur"* ]] && COMPREPLY+=("$word")
+                    done
+                    ;;
+            esac
+            ;;
+    esac
+}
+
+complete -F _my_tool 'my-tool'
+"#
+        );
+    }
+
+    #[test]
+    fn bash_should_quote_words_and_patterns() {
+        let script = bash("my tool", &get_special_scope());
+
+        assert!(script.contains("for word in '$(rm)' 'a|b'; do"));
+        assert!(script.contains("'$(rm)'|'a|b')"));
+        assert!(script.contains("complete -F _my_tool 'my tool'"));
+        assert!(!script.contains("compgen"));
+    }
+
+    #[test]
+    fn should_generate_zsh_completion() {
+        assert_eq!(
+            zsh("my-tool", &get_test_scope()),
+            r#"#compdef my-tool
+
+_my_tool() {
+    if (( CURRENT == 2 )); then
+        local -a commands
+        commands=(
+            'greet:Greet someone'\''s friend'
+            'hi:Greet someone'\''s friend'
+            'quit:'
+            'db:Database'
+        )
+
+        _describe 'command' commands
+        return
+    fi
+
+    case $words[2] in
+        'greet'|'hi')
+            compadd -- -l --loud --times
+            ;;
+        'db')
+            if (( CURRENT == 3 )); then
+                local -a commands
+                commands=(
+                    'query:Run a query'
+                )
+
+                _describe 'command' commands
+                return
+            fi
+
+            case $words[3] in
+                'query')
+                    compadd -- -name
+                    ;;
+            esac
+            ;;
+    esac
+}
+
+if [ "$funcstack[1]" = "_my_tool" ]; then
+    _my_tool "$@"
+else
+    compdef _my_tool 'my-tool'
+fi
+"#
+        );
+    }
+
+    #[test]
+    fn zsh_should_only_tag_plain_program_names() {
+        let script = zsh("my tool", &get_special_scope());
+
+        assert!(!script.contains("#compdef"));
+        assert!(script.contains("compdef _my_tool 'my tool'"));
+        assert!(script.contains("'$(rm)'|'a|b')"));
+    }
+
+    #[test]
+    fn should_generate_fish_completion() {
+        assert_eq!(
+            fish("my-tool", &get_test_scope()),
+            r#"complete -c 'my-tool' -f
+complete -c 'my-tool' -n __fish_use_subcommand -a 'greet' -d 'Greet someone\'s friend'
+complete -c 'my-tool' -n __fish_use_subcommand -a 'hi' -d 'Greet someone\'s friend'
+complete -c 'my-tool' -n __fish_use_subcommand -a 'quit'
+complete -c 'my-tool' -n __fish_use_subcommand -a 'db' -d 'Database'
+complete -c 'my-tool' -n '__fish_seen_subcommand_from greet hi' -s 'l'
+complete -c 'my-tool' -n '__fish_seen_subcommand_from greet hi' -l 'loud'
+complete -c 'my-tool' -n '__fish_seen_subcommand_from greet hi' -l 'times'
+complete -c 'my-tool' -n 'test (count (commandline -opc)) -eq 2; and test (commandline -opc)[2] = db' -a 'query' -d 'Run a query'
+complete -c 'my-tool' -n 'test (count (commandline -opc)) -gt 2; and test (commandline -opc)[2] = db; and contains -- (commandline -opc)[3] query' -o 'name'
+"#
+        );
+    }
+
+    #[test]
+    fn fish_should_quote_names_in_conditions() {
+        let script = fish("my-tool", &get_special_scope());
+
+        assert!(
+            script.contains("-n '__fish_seen_subcommand_from \\'$(rm)\\' \\'a|b\\'' -l 'force'")
+        );
+    }
+}
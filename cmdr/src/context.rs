@@ -0,0 +1,82 @@
+//! Context is application state that is shared by all scopes of a runner
+use crate::result::{Error, Result};
+use std::any::{type_name, Any};
+use std::fmt::{Debug, Formatter};
+use std::ops::{Deref, DerefMut};
+
+/// Application state owned by the runner, like database handles or configuration. Set it with
+/// `Runner::with_context` and take `context: &mut Context<T>` as a command argument to use it in
+/// every scope the runner runs, including sub scopes and the global scope.
+pub struct Context<T> {
+    value: T,
+}
+
+impl<T: Any> Context<T> {
+    pub(crate) fn new(value: T) -> Self {
+        Context { value }
+    }
+
+    /// The context passed to a command, an error when there is none or when it is not a `T`.
+    /// Used by the code generated for commands with a context argument
+    pub fn downcast<'a>(context: &'a mut Option<&mut dyn Any>) -> Result<&'a mut Context<T>> {
+        context
+            .as_mut()
+            .and_then(|context| context.downcast_mut())
+            .ok_or_else(|| Error::NoContext(type_name::<T>().to_string()))
+    }
+
+    /// Return the value of the context
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T> Deref for Context<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T> DerefMut for Context<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+}
+
+impl<T: Debug> Debug for Context<T> {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> std::fmt::Result {
+        formatter.debug_tuple("Context").field(&self.value).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_downcast_context_of_the_right_type() {
+        let mut context = Context::new(42u32);
+        let mut any: Option<&mut dyn Any> = Some(&mut context);
+
+        **Context::<u32>::downcast(&mut any).unwrap() += 1;
+
+        assert_eq!(*context, 43);
+    }
+
+    #[test]
+    fn should_fail_without_context_of_the_right_type() {
+        let mut context = Context::new("text");
+        let mut any: Option<&mut dyn Any> = Some(&mut context);
+
+        assert_eq!(
+            Context::<u32>::downcast(&mut any).unwrap_err(),
+            Error::NoContext("u32".to_string())
+        );
+        assert_eq!(
+            Context::<u32>::downcast(&mut None).unwrap_err(),
+            Error::NoContext("u32".to_string())
+        );
+    }
+}
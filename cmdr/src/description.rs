@@ -1,12 +1,29 @@
+use crate::format::{format_list, format_text, DEFAULT_WIDTH};
+use crate::messages::{DefaultMessages, Messages};
 use crate::{result::Error, Line};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 use std::fmt::{Debug, Error as FmtError, Formatter};
 
 /// Metadata describing a scope, is used to return help text and the list of commands that this
 /// scope exposes.
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct ScopeDescription {
+    #[cfg_attr(feature = "serde", serde(rename = "help"))]
     scope_help: Option<String>,
+    #[cfg_attr(feature = "serde", serde(rename = "commands"))]
     methods: Vec<ScopeCmdDescription>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    mounts: Vec<(String, ScopeDescription)>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    sections: Vec<(String, ScopeDescription)>,
 }
 
 impl ScopeDescription {
@@ -15,9 +32,76 @@ impl ScopeDescription {
         ScopeDescription {
             scope_help,
             methods,
+            mounts: Vec::new(),
+            sections: Vec::new(),
         }
     }
 
+    /// Mount the commands of another scope, they can be run by prefixing them with name like
+    /// `name command`. Adds a command called name that runs the mounted commands
+    pub fn with_mount(mut self, name: &str, description: ScopeDescription) -> Self {
+        self.methods.push(
+            ScopeCmdDescription::new(name.to_string(), vec![], description.scope_help.clone())
+                .with_usage("<command>"),
+        );
+        self.mounts.push((name.to_string(), description));
+        self
+    }
+
+    /// Add a mounted scope without the command that runs it, for descriptions that already have
+    /// the command
+    pub(crate) fn with_mounted_scope(mut self, name: &str, description: ScopeDescription) -> Self {
+        self.mounts.push((name.to_string(), description));
+        self
+    }
+
+    /// The sections with extra commands and their headers
+    pub(crate) fn sections(&self) -> impl Iterator<Item = (&str, &ScopeDescription)> {
+        self.sections
+            .iter()
+            .map(|(header, description)| (header.as_str(), description))
+    }
+
+    /// The description of the scope mounted as name
+    pub fn mount(&self, name: &str) -> Option<&ScopeDescription> {
+        self.mounts
+            .iter()
+            .find(|(mount, _)| mount == name)
+            .map(|(_, description)| description)
+    }
+
+    /// All mounted scopes with the names they are mounted as
+    pub fn mounts(&self) -> impl Iterator<Item = (&str, &ScopeDescription)> {
+        self.mounts
+            .iter()
+            .map(|(name, description)| (name.as_str(), description))
+    }
+
+    /// Add a section with extra commands to the help, like the commands that are available in
+    /// every scope. Commands in sections are only shown by help, they are not run by this scope
+    pub fn with_section(mut self, header: &str, description: ScopeDescription) -> Self {
+        self.sections.push((header.to_string(), description));
+        self
+    }
+
+    /// Help text accessor method
+    pub fn help_text(&self) -> &Option<String> {
+        &self.scope_help
+    }
+
+    /// Add commands that are not handled by this scope already
+    pub(crate) fn with_extra_commands<'a>(
+        mut self,
+        commands: impl Iterator<Item = &'a ScopeCmdDescription>,
+    ) -> Self {
+        for command in commands {
+            if self.command_by_name(command.name()).is_none() {
+                self.methods.push(command.clone());
+            }
+        }
+        self
+    }
+
     /// Get all scope commands
     pub fn all_commands(&self) -> impl Iterator<Item = &ScopeCmdDescription> {
         self.methods.iter()
@@ -25,10 +109,7 @@ impl ScopeDescription {
 
     /// Find a command method by its command name or alias
     fn command_by_name(&self, name: &str) -> Option<&ScopeCmdDescription> {
-        self.methods
-            .iter()
-            .filter(|method| method.handles(name))
-            .next()
+        self.methods.iter().find(|method| method.handles(name))
     }
 
     /// Return a command for an entered line
@@ -39,41 +120,116 @@ impl ScopeDescription {
 
     /// Format help text for command
     pub fn format_help_text(&self, command: Option<&str>) -> Result<String, Error> {
+        self.format_help(command, DEFAULT_WIDTH, &DefaultMessages)
+    }
+
+    /// Format help text for command wrapped to fit in width, using messages for the texts built
+    /// into cmdr
+    pub fn format_help(
+        &self,
+        command: Option<&str>,
+        width: usize,
+        messages: &dyn Messages,
+    ) -> Result<String, Error> {
         if let Some(command) = command {
-            Ok(self
+            if let Some(mount) = self.mount(command) {
+                return Ok(mount.format_scope_help(width, messages));
+            }
+
+            let description = self
                 .command_by_name(command)
-                .ok_or(Error::InvalidCommand(command.to_string()))?
+                .or_else(|| {
+                    self.sections
+                        .iter()
+                        .find_map(|(_, section)| section.command_by_name(command))
+                })
+                .ok_or_else(|| Error::InvalidCommand(command.to_string()))?;
+
+            let help_text = description
                 .help_text
-                .clone()
-                .ok_or(Error::NoHelpForCommand(command.to_string()))?)
+                .as_ref()
+                .ok_or_else(|| Error::NoHelpForCommand(command.to_string()))?;
+
+            match description.usage() {
+                Some(usage) => Ok(format!(
+                    "{}\n\n{}",
+                    messages.usage(description.name(), usage),
+                    format_text(help_text, width)
+                )),
+                None => Ok(format_text(help_text, width)),
+            }
         } else {
-            Ok(self.format_scope_help())
+            Ok(self.format_scope_help(width, messages))
         }
     }
 
-    fn format_scope_help(&self) -> String {
-        let mut result = String::new();
+    fn format_scope_help(&self, width: usize, messages: &dyn Messages) -> String {
+        let header = match &self.scope_help {
+            Some(scope_help) => scope_help.clone(),
+            None => messages.help_header(),
+        };
+
+        let commands: Vec<(&str, &str)> = self
+            .all_commands()
+            .filter(|command| self.mount(command.name()).is_none())
+            .map(|command| (command.name(), command.summary()))
+            .collect();
+
+        let mut help = format_text(&header, width);
+        if !commands.is_empty() {
+            help.push_str("\n\n");
+            help.push_str(&format_list(&commands, width));
+        }
+
+        for (name, mount) in self.mounts() {
+            let names: Vec<String> = mount
+                .all_commands()
+                .map(|command| format!("{} {}", name, command.name()))
+                .collect();
+            let commands: Vec<(&str, &str)> = names
+                .iter()
+                .zip(mount.all_commands())
+                .map(|(name, command)| (name.as_str(), command.summary()))
+                .collect();
 
-        result.push_str(match &self.scope_help {
-            Some(scope_help) => scope_help,
-            None => "These are the valid commands in this scope:",
-        });
+            help.push_str(&format!(
+                "\n\n{}\n\n{}",
+                format_text(&messages.mount_help_header(name), width),
+                format_list(&commands, width)
+            ));
+        }
 
-        result.push('\n');
+        for (header, section) in &self.sections {
+            let commands: Vec<(&str, &str)> = section
+                .all_commands()
+                .filter(|command| self.command_by_name(command.name()).is_none())
+                .map(|command| (command.name(), command.summary()))
+                .collect();
 
-        for command in self.all_commands() {
-            result = format!("{}- {}\n", result, command.name());
+            if !commands.is_empty() {
+                help.push_str(&format!(
+                    "\n\n{}\n\n{}",
+                    format_text(header, width),
+                    format_list(&commands, width)
+                ));
+            }
         }
 
-        result
+        help
     }
 }
 
 /// All information about a command method in one handy struct
+#[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct ScopeCmdDescription {
     name: String,
+    #[cfg_attr(feature = "serde", serde(rename = "aliases", default))]
     alias: Vec<String>,
+    #[cfg_attr(feature = "serde", serde(rename = "help", default))]
     help_text: Option<String>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    usage: Option<String>,
 }
 
 impl ScopeCmdDescription {
@@ -83,9 +239,16 @@ impl ScopeCmdDescription {
             name,
             alias,
             help_text,
+            usage: None,
         }
     }
 
+    /// Set a usage line describing the arguments of the command, for example `<name> [greeting]`
+    pub fn with_usage(mut self, usage: &str) -> Self {
+        self.usage = Some(usage.to_string());
+        self
+    }
+
     /// Name accessor method
     pub fn name(&self) -> &str {
         self.name.as_ref()
@@ -96,6 +259,19 @@ impl ScopeCmdDescription {
         &self.help_text
     }
 
+    /// Usage line accessor method
+    pub fn usage(&self) -> Option<&str> {
+        self.usage.as_deref()
+    }
+
+    /// The first line of the help text, or an empty string when there is no help text
+    pub fn summary(&self) -> &str {
+        self.help_text
+            .as_ref()
+            .and_then(|help_text| help_text.lines().next())
+            .unwrap_or("")
+    }
+
     /// An iterator of all aliasses for this command
     pub fn aliases(&self) -> impl Iterator<Item = &String> {
         self.alias.iter()
@@ -103,23 +279,18 @@ impl ScopeCmdDescription {
 
     /// Checks name or alias to see if a command can be handled.
     pub fn handles(&self, command: &str) -> bool {
-        if self.name == command {
-            true
-        } else if self.alias.contains(&command.to_string()) {
-            true
-        } else {
-            false
-        }
+        self.name == command || self.alias.iter().any(|alias| alias == command)
     }
 }
 
-impl<'a> Debug for ScopeCmdDescription {
+impl Debug for ScopeCmdDescription {
     fn fmt(&self, formatter: &mut Formatter<'_>) -> Result<(), FmtError> {
         formatter
             .debug_struct("ScopeCmdDescription")
             .field("name", &self.name)
             .field("alias", &self.alias)
             .field("help_text", &self.help_text)
+            .field("usage", &self.usage)
             .finish()
     }
 }
@@ -159,6 +330,146 @@ mod tests {
         )
     }
 
+    #[test]
+    fn command_should_return_first_line_of_help_text_as_summary() {
+        let command = get_test_command();
+
+        assert_eq!(command.summary(), "Help text")
+    }
+
+    #[test]
+    fn scope_should_list_commands_with_summary() {
+        let scope = ScopeDescription::new(
+            None,
+            vec![
+                get_test_command(),
+                ScopeCmdDescription::new("quit".to_string(), vec![], None),
+            ],
+        );
+
+        assert_eq!(
+            scope.format_help_text(None),
+            Ok("These are the valid commands in this scope:\n\ntest  Help text\nquit".to_string())
+        )
+    }
+
+    #[test]
+    fn scope_should_return_help_text_for_command() {
+        let scope = ScopeDescription::new(None, vec![get_test_command()]);
+
+        assert_eq!(
+            scope.format_help_text(Some("alias1")),
+            Ok("Help text\nMore lines".to_string())
+        )
+    }
+
+    #[test]
+    fn scope_should_return_usage_with_help_text_for_command() {
+        let scope = ScopeDescription::new(None, vec![get_test_command().with_usage("<name>")]);
+
+        assert_eq!(
+            scope.format_help_text(Some("test")),
+            Ok("Usage: test <name>\n\nHelp text\nMore lines".to_string())
+        )
+    }
+
+    #[test]
+    fn scope_should_wrap_help_text_to_width() {
+        let scope = ScopeDescription::new(
+            Some("Commands to manage the files of the application".to_string()),
+            vec![],
+        );
+
+        assert_eq!(
+            scope.format_help(None, 20, &DefaultMessages),
+            Ok("Commands to manage\nthe files of the\napplication".to_string())
+        )
+    }
+
+    #[test]
+    fn scope_should_keep_layout_of_help_text() {
+        let scope = ScopeDescription::new(
+            None,
+            vec![ScopeCmdDescription::new(
+                "add".to_string(),
+                vec![],
+                Some(
+                    "Add numbers\n\nExamples:\n  add 1 2     prints 3\n  add 1 2 3   prints 6"
+                        .to_string(),
+                ),
+            )],
+        );
+
+        assert_eq!(
+            scope.format_help_text(Some("add")),
+            Ok(
+                "Add numbers\n\nExamples:\n  add 1 2     prints 3\n  add 1 2 3   prints 6"
+                    .to_string()
+            )
+        )
+    }
+
+    #[test]
+    fn scope_should_list_commands_of_sections_separately() {
+        let scope = ScopeDescription::new(None, vec![get_test_command()]).with_section(
+            "These commands are available in every scope:",
+            ScopeDescription::new(
+                None,
+                vec![
+                    ScopeCmdDescription::new("quit".to_string(), vec![], Some("Quit".to_string())),
+                    ScopeCmdDescription::new("test".to_string(), vec![], None),
+                ],
+            ),
+        );
+
+        assert_eq!(
+            scope.format_help_text(None),
+            Ok(
+                "These are the valid commands in this scope:\n\ntest  Help text\n\n\
+                These commands are available in every scope:\n\nquit  Quit"
+                    .to_string()
+            )
+        );
+        assert_eq!(scope.format_help_text(Some("quit")), Ok("Quit".to_string()));
+        assert!(scope
+            .command_for_line(&Line::try_parse("quit").unwrap())
+            .is_none());
+    }
+
+    #[test]
+    fn scope_should_list_mounted_commands_in_sections() {
+        let scope = ScopeDescription::new(None, vec![get_test_command()]).with_mount(
+            "db",
+            ScopeDescription::new(
+                Some("Database commands".to_string()),
+                vec![ScopeCmdDescription::new(
+                    "query".to_string(),
+                    vec![],
+                    Some("Run a query".to_string()),
+                )],
+            ),
+        );
+
+        assert_eq!(
+            scope.format_help_text(None),
+            Ok(
+                "These are the valid commands in this scope:\n\ntest  Help text\n\n\
+                Commands of db:\n\ndb query  Run a query"
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            scope.format_help_text(Some("db")),
+            Ok("Database commands\n\nquery  Run a query".to_string())
+        );
+        assert_eq!(
+            scope
+                .command_for_line(&Line::try_parse("db query").unwrap())
+                .map(ScopeCmdDescription::name),
+            Some("db")
+        );
+    }
+
     #[test]
     fn command_should_return_all_aliases() {
         let command = get_test_command();
@@ -181,4 +492,31 @@ mod tests {
         assert!(command.handles("alias1"));
         assert!(command.handles("alias2"));
     }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn scope_should_serialize_and_deserialize() {
+        let scope = ScopeDescription::new(
+            Some("Scope help".to_string()),
+            vec![get_test_command().with_usage("<name>")],
+        );
+        let json = serde_json::to_value(&scope).unwrap();
+
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "help": "Scope help",
+                "commands": [{
+                    "name": "test",
+                    "aliases": ["alias1", "alias2"],
+                    "help": "Help text\nMore lines",
+                    "usage": "<name>",
+                }],
+            })
+        );
+        assert_eq!(
+            serde_json::from_value::<ScopeDescription>(json).unwrap(),
+            scope
+        );
+    }
 }
@@ -0,0 +1,226 @@
+//! Generate reference documentation for scopes as Markdown or as roff man pages.
+//!
+//! The documentation contains the synopsis, aliases and help text of every command in a scope
+//! and in the sub scopes it declares with `Scope::sub_scopes`. Call these functions from a build
+//! script or from a command of the application.
+use crate::description::ScopeDescription;
+use crate::scope::Scope;
+
+/// A scope and its sub scopes, with the command path that opens them
+struct DocScope {
+    path: String,
+    description: ScopeDescription,
+}
+
+/// Collect a scope and the sub scopes it declares
+fn collect<S: Scope + ?Sized>(name: &str, scope: &S) -> Vec<DocScope> {
+    let mut scopes = vec![DocScope {
+        path: name.to_string(),
+        description: scope.commands(),
+    }];
+
+    scopes.extend(
+        scope
+            .sub_scopes()
+            .into_iter()
+            .map(|(command, description)| DocScope {
+                path: format!("{} {}", name, command),
+                description,
+            }),
+    );
+    scopes
+}
+
+/// Generate Markdown documentation for a scope and its sub scopes
+pub fn markdown<S: Scope + ?Sized>(name: &str, scope: &S) -> String {
+    let mut doc = String::new();
+
+    for scope in collect(name, scope) {
+        doc.push_str(&format!("# {}\n\n", scope.path));
+        if let Some(help_text) = scope.description.help_text() {
+            doc.push_str(&format!("{}\n\n", help_text));
+        }
+
+        for command in scope.description.all_commands() {
+            doc.push_str(&format!("## {}\n\n", command.name()));
+
+            let synopsis = match command.usage() {
+                Some(usage) => format!("{} {}", command.name(), usage),
+                None => command.name().to_string(),
+            };
+            doc.push_str(&format!("```text\n{}\n```\n\n", synopsis));
+
+            let aliases: Vec<String> = command
+                .aliases()
+                .map(|alias| format!("`{}`", alias))
+                .collect();
+            if !aliases.is_empty() {
+                doc.push_str(&format!("Aliases: {}\n\n", aliases.join(", ")));
+            }
+
+            if let Some(help_text) = command.help_text() {
+                doc.push_str(&format!("{}\n\n", help_text));
+            }
+        }
+    }
+
+    doc.truncate(doc.trim_end().len());
+    doc.push('\n');
+    doc
+}
+
+/// Generate a roff man page for a scope and its sub scopes, can be viewed with `man -l`
+pub fn man_page<S: Scope + ?Sized>(name: &str, scope: &S) -> String {
+    let scopes = collect(name, scope);
+    let mut doc = format!(
+        ".TH {} 1\n.SH NAME\n{}\n",
+        escape(&name.to_uppercase()),
+        escape(name)
+    );
+
+    if let Some(help_text) = scopes[0].description.help_text() {
+        doc.push_str(".SH DESCRIPTION\n");
+        doc.push_str(&paragraphs(help_text));
+    }
+
+    for (index, scope) in scopes.iter().enumerate() {
+        if index == 0 {
+            doc.push_str(".SH COMMANDS\n");
+        } else {
+            doc.push_str(&format!(
+                ".SH \"{} COMMANDS\"\n",
+                escape(&scope.path.to_uppercase())
+            ));
+            if let Some(help_text) = scope.description.help_text() {
+                doc.push_str(&paragraphs(help_text));
+            }
+        }
+
+        for command in scope.description.all_commands() {
+            doc.push_str(&format!(".TP\n\\fB{}\\fR", escape(command.name())));
+            if let Some(usage) = command.usage() {
+                doc.push_str(&format!(" {}", escape(usage)));
+            }
+            doc.push('\n');
+
+            if let Some(help_text) = command.help_text() {
+                doc.push_str(&paragraphs(help_text));
+            }
+
+            let aliases: Vec<&str> = command.aliases().map(|alias| alias.as_str()).collect();
+            if !aliases.is_empty() {
+                doc.push_str(&format!(".br\nAliases: {}\n", escape(&aliases.join(", "))));
+            }
+        }
+    }
+
+    doc
+}
+
+/// Format help text as roff, empty lines start a new paragraph
+fn paragraphs(text: &str) -> String {
+    text.lines()
+        .map(|line| match line.trim() {
+            "" => ".sp\n".to_string(),
+            line => format!("{}\n", escape(line)),
+        })
+        .collect()
+}
+
+/// Escape text so roff does not interpret it as requests or escapes
+fn escape(text: &str) -> String {
+    let escaped = text
+        .replace('\\', "\\e")
+        .replace('-', "\\-")
+        .replace('"', "\\(dq");
+
+    if escaped.starts_with('.') || escaped.starts_with('\'') {
+        format!("\\&{}", escaped)
+    } else {
+        escaped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::description::ScopeCmdDescription;
+    use crate::interaction::Interaction;
+    use crate::result::{Action, CommandResult};
+
+    struct MainScope {}
+
+    impl Scope for MainScope {
+        fn commands(&self) -> ScopeDescription {
+            ScopeDescription::new(
+                Some("The main scope".to_string()),
+                vec![ScopeCmdDescription::new(
+                    "greet".to_string(),
+                    vec!["hi".to_string()],
+                    Some("Greet someone".to_string()),
+                )
+                .with_usage("<name>")],
+            )
+        }
+
+        fn run_command(
+            &mut self,
+            _command: &ScopeCmdDescription,
+            _args: &[String],
+            _interaction: &mut Interaction,
+        ) -> CommandResult {
+            Ok(Action::Done)
+        }
+
+        fn sub_scopes(&self) -> Vec<(String, ScopeDescription)> {
+            vec![("user".to_string(), UserScope {}.commands())]
+        }
+    }
+
+    struct UserScope {}
+
+    impl Scope for UserScope {
+        fn commands(&self) -> ScopeDescription {
+            ScopeDescription::new(
+                None,
+                vec![ScopeCmdDescription::new("--add".to_string(), vec![], None)],
+            )
+        }
+
+        fn run_command(
+            &mut self,
+            _command: &ScopeCmdDescription,
+            _args: &[String],
+            _interaction: &mut Interaction,
+        ) -> CommandResult {
+            Ok(Action::Done)
+        }
+    }
+
+    #[test]
+    fn should_generate_markdown() {
+        assert_eq!(
+            markdown("app", &MainScope {}),
+            "# app\n\nThe main scope\n\n\
+             ## greet\n\n```text\ngreet <name>\n```\n\nAliases: `hi`\n\nGreet someone\n\n\
+             # app user\n\n## --add\n\n```text\n--add\n```\n"
+        );
+    }
+
+    #[test]
+    fn should_generate_man_page() {
+        assert_eq!(
+            man_page("app", &MainScope {}),
+            ".TH APP 1\n.SH NAME\napp\n.SH DESCRIPTION\nThe main scope\n\
+             .SH COMMANDS\n.TP\n\\fBgreet\\fR <name>\nGreet someone\n.br\nAliases: hi\n\
+             .SH \"APP USER COMMANDS\"\n.TP\n\\fB\\-\\-add\\fR\n"
+        );
+    }
+
+    #[test]
+    fn should_escape_roff() {
+        assert_eq!(escape(".start"), "\\&.start");
+        assert_eq!(escape("back\\slash"), "back\\eslash");
+        assert_eq!(escape("say \"hi\""), "say \\(dqhi\\(dq");
+    }
+}
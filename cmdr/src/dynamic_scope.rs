@@ -0,0 +1,327 @@
+//! A scope with commands that are registered at runtime
+use crate::description::{ScopeCmdDescription, ScopeDescription};
+use crate::interaction::Interaction;
+use crate::result::{CommandResult, Error};
+use crate::scope::Scope;
+use std::cell::RefCell;
+use std::fmt::{Debug, Formatter};
+use std::rc::Rc;
+
+/// Function that runs a dynamic command with the arguments that were entered
+type CommandFn = Box<dyn FnMut(&[String], &mut Interaction) -> CommandResult>;
+
+/// A registered command, shared so it can run while the registry is changed
+type Command = Rc<RefCell<CommandFn>>;
+
+/// A scope with commands that are closures registered at runtime, for example commands read from
+/// configuration or added by plugins. Commands can be added and removed while the application
+/// runs through a `CommandRegistry` of the scope, changes are used from the next prompt on. A
+/// help command is available unless a command called help is added.
+///
+/// ```
+/// use cmdr::*;
+///
+/// let scope = DynamicScope::new().with_command(
+///     ScopeCmdDescription::new("greet".to_string(), vec![], Some("Greet someone".to_string()))
+///         .with_usage("<name>"),
+///     |args, interaction| {
+///         interaction.write_line(&format!("Hello {}", args.join(" ")));
+///         Ok(Action::Done)
+///     },
+/// );
+/// ```
+pub struct DynamicScope {
+    help_text: Option<String>,
+    prompt: Option<String>,
+    registry: CommandRegistry,
+}
+
+impl DynamicScope {
+    /// Create a scope without commands
+    pub fn new() -> Self {
+        DynamicScope {
+            help_text: None,
+            prompt: None,
+            registry: CommandRegistry::default(),
+        }
+    }
+
+    /// Set the help text of the scope
+    pub fn with_help(mut self, help_text: &str) -> Self {
+        self.help_text = Some(help_text.to_string());
+        self
+    }
+
+    /// Set the prompt of the scope
+    pub fn with_prompt(mut self, prompt: &str) -> Self {
+        self.prompt = Some(prompt.to_string());
+        self
+    }
+
+    /// Add a command, see `add_command`
+    pub fn with_command<F>(mut self, description: ScopeCmdDescription, command: F) -> Self
+    where
+        F: FnMut(&[String], &mut Interaction) -> CommandResult + 'static,
+    {
+        self.add_command(description, command);
+        self
+    }
+
+    /// Add a command that runs the closure, replaces the command with the same name when there
+    /// is one. The description has the name, aliases, help text and usage of the command
+    pub fn add_command<F>(&mut self, description: ScopeCmdDescription, command: F)
+    where
+        F: FnMut(&[String], &mut Interaction) -> CommandResult + 'static,
+    {
+        self.registry.add_command(description, command)
+    }
+
+    /// Remove the command with name, returns false when there was no such command
+    pub fn remove_command(&mut self, name: &str) -> bool {
+        self.registry.remove_command(name)
+    }
+
+    /// Check if a command with name is registered
+    pub fn has_command(&self, name: &str) -> bool {
+        self.registry.has_command(name)
+    }
+
+    /// The commands of this scope, commands can capture it to add or remove commands while the
+    /// scope runs
+    pub fn registry(&self) -> CommandRegistry {
+        self.registry.clone()
+    }
+
+    /// Check if the built-in help command is used
+    fn has_help(&self) -> bool {
+        !self
+            .registry
+            .commands
+            .borrow()
+            .iter()
+            .any(|(description, _)| description.handles("help"))
+    }
+}
+
+impl Default for DynamicScope {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Scope for DynamicScope {
+    fn commands(&self) -> ScopeDescription {
+        let help = if self.has_help() {
+            Some(ScopeCmdDescription::new("help".to_string(), vec![], None))
+        } else {
+            None
+        };
+
+        ScopeDescription::new(
+            self.help_text.clone(),
+            help.into_iter()
+                .chain(self.registry.descriptions())
+                .collect(),
+        )
+    }
+
+    fn run_command(
+        &mut self,
+        command: &ScopeCmdDescription,
+        args: &[String],
+        interaction: &mut Interaction,
+    ) -> CommandResult {
+        if command.name() == "help" && self.has_help() {
+            return self.help_with(interaction, args);
+        }
+
+        // The registry is not borrowed while the command runs, so the command can change it
+        let run = self.registry.find(command.name());
+        match run {
+            Some(run) => (*run.borrow_mut())(args, interaction),
+            None => Err(Error::InvalidCommand(command.name().to_string())),
+        }
+    }
+
+    fn prompt(&self) -> String {
+        match &self.prompt {
+            Some(prompt) => prompt.clone(),
+            None => ">".to_string(),
+        }
+    }
+}
+
+impl Debug for DynamicScope {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> std::fmt::Result {
+        formatter
+            .debug_struct("DynamicScope")
+            .field("help_text", &self.help_text)
+            .field("prompt", &self.prompt)
+            .field("commands", &self.registry)
+            .finish()
+    }
+}
+
+/// The commands of a `DynamicScope`. Clones share the same commands, a command can capture a clone
+/// to add or remove commands of its own scope while it runs
+///
+/// ```
+/// use cmdr::*;
+///
+/// let mut scope = DynamicScope::new();
+/// let registry = scope.registry();
+/// scope.add_command(
+///     ScopeCmdDescription::new("unlock".to_string(), vec![], None),
+///     move |_, _| {
+///         registry.add_command(
+///             ScopeCmdDescription::new("secret".to_string(), vec![], None),
+///             |_, interaction| {
+///                 interaction.write_line("42");
+///                 Ok(Action::Done)
+///             },
+///         );
+///         Ok(Action::Done)
+///     },
+/// );
+/// ```
+#[derive(Clone, Default)]
+pub struct CommandRegistry {
+    commands: Rc<RefCell<Vec<(ScopeCmdDescription, Command)>>>,
+}
+
+impl CommandRegistry {
+    /// Add a command that runs the closure, replaces the command with the same name when there
+    /// is one
+    pub fn add_command<F>(&self, description: ScopeCmdDescription, command: F)
+    where
+        F: FnMut(&[String], &mut Interaction) -> CommandResult + 'static,
+    {
+        self.remove_command(description.name());
+        let command: CommandFn = Box::new(command);
+        self.commands
+            .borrow_mut()
+            .push((description, Rc::new(RefCell::new(command))));
+    }
+
+    /// Remove the command with name, returns false when there was no such command
+    pub fn remove_command(&self, name: &str) -> bool {
+        let mut commands = self.commands.borrow_mut();
+        let count = commands.len();
+        commands.retain(|(description, _)| description.name() != name);
+
+        commands.len() != count
+    }
+
+    /// Check if a command with name is registered
+    pub fn has_command(&self, name: &str) -> bool {
+        self.commands
+            .borrow()
+            .iter()
+            .any(|(description, _)| description.name() == name)
+    }
+
+    /// Descriptions of the registered commands
+    fn descriptions(&self) -> Vec<ScopeCmdDescription> {
+        self.commands
+            .borrow()
+            .iter()
+            .map(|(description, _)| description.clone())
+            .collect()
+    }
+
+    /// The command with name
+    fn find(&self, name: &str) -> Option<Command> {
+        self.commands
+            .borrow()
+            .iter()
+            .find(|(description, _)| description.name() == name)
+            .map(|(_, command)| command.clone())
+    }
+}
+
+impl Debug for CommandRegistry {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> std::fmt::Result {
+        formatter.debug_list().entries(self.descriptions()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::line::Line;
+    use crate::line_reader::FileLineReader;
+    use crate::line_writer::LineWriter;
+    use crate::result::Action;
+
+    fn get_test_scope() -> DynamicScope {
+        DynamicScope::new().with_command(
+            ScopeCmdDescription::new("greet".to_string(), vec!["hi".to_string()], None),
+            |args, interaction| {
+                interaction.write_line(&format!("Hello {}", args.join(" ")));
+                Ok(Action::Done)
+            },
+        )
+    }
+
+    /// Run a line on the scope and return the output
+    fn run(scope: &mut DynamicScope, line: &str) -> (CommandResult, String) {
+        let mut output = String::new();
+        let line = Line::try_parse(line).unwrap();
+        let commands = scope.commands();
+        let result = match commands.command_for_line(&line) {
+            Some(command) => scope.run_command(
+                command,
+                &line.args,
+                &mut Interaction::new(&mut FileLineReader::new("".as_bytes()), &mut output),
+            ),
+            None => Err(Error::InvalidCommand(line.command)),
+        };
+
+        (result, output)
+    }
+
+    #[test]
+    fn should_run_registered_command_by_alias() {
+        let (result, output) = run(&mut get_test_scope(), "hi Bob");
+
+        assert!(matches!(result, Ok(Action::Done)));
+        assert_eq!(output, "Hello Bob\n");
+    }
+
+    #[test]
+    fn should_remove_command() {
+        let mut scope = get_test_scope();
+
+        assert!(scope.remove_command("greet"));
+        assert!(!scope.remove_command("greet"));
+        assert_eq!(
+            run(&mut scope, "greet Bob").0.unwrap_err(),
+            Error::InvalidCommand("greet".to_string())
+        );
+    }
+
+    #[test]
+    fn should_replace_command_with_same_name() {
+        let mut scope = get_test_scope();
+        scope.add_command(
+            ScopeCmdDescription::new("greet".to_string(), vec![], None),
+            |_, interaction| {
+                interaction.write_line("Hi");
+                Ok(Action::Done)
+            },
+        );
+
+        assert_eq!(run(&mut scope, "greet").1, "Hi\n");
+        assert_eq!(scope.commands().all_commands().count(), 2);
+    }
+
+    #[test]
+    fn should_list_commands_in_help() {
+        let (_, output) = run(&mut get_test_scope(), "help");
+
+        assert_eq!(
+            output,
+            "\nThese are the valid commands in this scope:\n\nhelp\ngreet\n"
+        );
+    }
+}
@@ -0,0 +1,250 @@
+//! Text formatting helpers for rendering tables, lists and paragraphs
+
+/// Width used when the width of the output is not known
+pub(crate) const DEFAULT_WIDTH: usize = 80;
+
+/// Values in a list are never wrapped to a width smaller than this
+const MIN_VALUE_WIDTH: usize = 20;
+
+/// Table columns are never made narrower than this to fit a table in the width
+const MIN_COLUMN_WIDTH: usize = 8;
+
+/// Number of characters in a string
+fn text_width(text: &str) -> usize {
+    text.chars().count()
+}
+
+/// Wrap text into lines no longer than width, existing line breaks are kept and words longer
+/// than width are not broken up
+pub(crate) fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+
+    for paragraph in text.lines() {
+        let mut line = String::new();
+
+        for word in paragraph.split_whitespace() {
+            if !line.is_empty() && text_width(&line) + 1 + text_width(word) > width {
+                lines.push(line);
+                line = String::new();
+            }
+
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(word);
+        }
+
+        lines.push(line);
+    }
+
+    lines
+}
+
+/// Format a table with a header row. Every column is as wide as its widest cell, when the table
+/// does not fit in width the widest columns are made narrower and their cells are wrapped
+pub(crate) fn format_table(headers: &[&str], rows: &[Vec<String>], width: usize) -> String {
+    let mut widths: Vec<usize> = headers
+        .iter()
+        .enumerate()
+        .map(|(column, header)| {
+            rows.iter()
+                .filter_map(|row| row.get(column))
+                .map(|cell| text_width(cell))
+                .chain(Some(text_width(header)))
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    let separators = 2 * widths.len().saturating_sub(1);
+    while widths.iter().sum::<usize>() + separators > width {
+        match widths.iter_mut().max() {
+            Some(widest) if *widest > MIN_COLUMN_WIDTH => *widest -= 1,
+            _ => break,
+        }
+    }
+
+    let header: Vec<String> = headers.iter().map(|header| header.to_string()).collect();
+
+    Some(&header)
+        .into_iter()
+        .chain(rows)
+        .map(|row| format_row(row, &widths))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Format a single table row, cells that are wider than their column are wrapped over several
+/// lines
+fn format_row(row: &[String], widths: &[usize]) -> String {
+    let cells: Vec<Vec<String>> = row
+        .iter()
+        .zip(widths)
+        .map(|(cell, width)| wrap(cell, *width))
+        .collect();
+    let height = cells.iter().map(Vec::len).max().unwrap_or(0).max(1);
+
+    (0..height)
+        .map(|line| {
+            cells
+                .iter()
+                .zip(widths)
+                .map(|(cell, width)| {
+                    let text = cell.get(line).map_or("", String::as_str);
+                    format!("{:width$}", text, width = width)
+                })
+                .collect::<Vec<_>>()
+                .join("  ")
+                .trim_end()
+                .to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Format a list of names and values, values are aligned and wrapped to fit in width
+pub(crate) fn format_list(items: &[(&str, &str)], width: usize) -> String {
+    let name_width = items
+        .iter()
+        .map(|(name, _)| text_width(name))
+        .max()
+        .unwrap_or(0);
+
+    let value_width = width.saturating_sub(name_width + 2).max(MIN_VALUE_WIDTH);
+
+    let mut lines = Vec::new();
+
+    for (name, value) in items {
+        let mut value_lines = wrap(value, value_width).into_iter();
+
+        let first = value_lines.next().unwrap_or_default();
+        lines.push(format!("{:width$}  {}", name, first, width = name_width));
+
+        for line in value_lines {
+            lines.push(format!("{:width$}  {}", "", line, width = name_width));
+        }
+    }
+
+    lines
+        .iter()
+        .map(|line| line.trim_end())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Format text as a paragraph wrapped to fit in width
+pub(crate) fn format_paragraph(text: &str, width: usize) -> String {
+    wrap(text, width).join("\n")
+}
+
+/// Format text that may be laid out by hand, like help texts. Lines that fit in width are kept as
+/// they are, longer lines are wrapped and their continuation lines keep the indentation
+pub(crate) fn format_text(text: &str, width: usize) -> String {
+    let mut lines = Vec::new();
+
+    for line in text.lines().map(str::trim_end) {
+        if text_width(line) <= width {
+            lines.push(line.to_string());
+            continue;
+        }
+
+        let content = line.trim_start();
+        let indent = &line[..line.len() - content.len()];
+        for wrapped in wrap(content, width.saturating_sub(text_width(indent))) {
+            lines.push(format!("{}{}", indent, wrapped));
+        }
+    }
+
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wrap_should_break_lines_between_words() {
+        assert_eq!(
+            wrap("one two three four", 9),
+            vec!["one two", "three", "four"]
+        );
+    }
+
+    #[test]
+    fn wrap_should_keep_existing_line_breaks() {
+        assert_eq!(wrap("one\ntwo three", 80), vec!["one", "two three"]);
+    }
+
+    #[test]
+    fn wrap_should_not_break_long_words() {
+        assert_eq!(wrap("a verylongword", 5), vec!["a", "verylongword"]);
+    }
+
+    #[test]
+    fn text_should_keep_lines_that_fit() {
+        assert_eq!(
+            format_text("Usage:\n  add  <a>  <b>\n\nAdds numbers", 80),
+            "Usage:\n  add  <a>  <b>\n\nAdds numbers"
+        );
+    }
+
+    #[test]
+    fn text_should_wrap_long_lines_with_their_indentation() {
+        assert_eq!(
+            format_text("Options:\n  one two three four", 12),
+            "Options:\n  one two\n  three four"
+        );
+    }
+
+    #[test]
+    fn table_should_align_columns() {
+        assert_eq!(
+            format_table(
+                &["name", "age"],
+                &[
+                    vec!["Bob".to_string(), "42".to_string()],
+                    vec!["Alice".to_string(), "7".to_string()]
+                ],
+                80
+            ),
+            "name   age\nBob    42\nAlice  7"
+        );
+    }
+
+    #[test]
+    fn table_should_wrap_widest_column_to_fit_width() {
+        assert_eq!(
+            format_table(
+                &["id", "description"],
+                &[vec![
+                    "1".to_string(),
+                    "one two three four five six".to_string()
+                ]],
+                20
+            ),
+            "id  description\n1   one two three\n    four five six"
+        );
+    }
+
+    #[test]
+    fn list_should_align_values() {
+        assert_eq!(
+            format_list(&[("name", "Bob"), ("admin", "false")], 80),
+            "name   Bob\nadmin  false"
+        );
+    }
+
+    #[test]
+    fn list_should_wrap_and_indent_long_values() {
+        assert_eq!(
+            format_list(
+                &[(
+                    "a",
+                    "one two three four five six seven eight nine ten eleven"
+                )],
+                24
+            ),
+            "a  one two three four\n   five six seven eight\n   nine ten eleven"
+        );
+    }
+}
@@ -0,0 +1,88 @@
+//! Futures for the run loop that `Runner` and `AsyncRunner` share
+use std::future::Future;
+use std::mem::ManuallyDrop;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+use std::thread::{self, Thread};
+
+/// A boxed future, returned by the async methods of cmdr traits
+pub type LocalBoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + 'a>>;
+
+/// Run a future on the current thread until it is done. The `Runner` runs the shared loop with
+/// this, without async commands its futures are ready right away
+pub(crate) fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = Box::pin(future);
+    let waker = thread_waker(thread::current());
+    let mut context = Context::from_waker(&waker);
+
+    loop {
+        match future.as_mut().poll(&mut context) {
+            Poll::Ready(output) => return output,
+            Poll::Pending => thread::park(),
+        }
+    }
+}
+
+/// A waker that unparks thread
+fn thread_waker(thread: Thread) -> Waker {
+    let data = Arc::into_raw(Arc::new(thread)) as *const ();
+    unsafe { Waker::from_raw(RawWaker::new(data, &VTABLE)) }
+}
+
+static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, wake, wake_by_ref, release);
+
+unsafe fn clone(data: *const ()) -> RawWaker {
+    let thread = ManuallyDrop::new(Arc::from_raw(data as *const Thread));
+    let clone: Arc<Thread> = Arc::clone(&thread);
+    RawWaker::new(Arc::into_raw(clone) as *const (), &VTABLE)
+}
+
+unsafe fn wake(data: *const ()) {
+    Arc::from_raw(data as *const Thread).unpark();
+}
+
+unsafe fn wake_by_ref(data: *const ()) {
+    (*(data as *const Thread)).unpark();
+}
+
+unsafe fn release(data: *const ()) {
+    drop(Arc::from_raw(data as *const Thread));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    /// Pending until it was polled once, wakes itself from another thread
+    struct Later(bool);
+
+    impl Future for Later {
+        type Output = u32;
+
+        fn poll(mut self: Pin<&mut Self>, context: &mut Context<'_>) -> Poll<u32> {
+            if self.0 {
+                return Poll::Ready(42);
+            }
+            self.0 = true;
+
+            let waker = context.waker().clone();
+            thread::spawn(move || {
+                thread::sleep(Duration::from_millis(10));
+                waker.wake();
+            });
+            Poll::Pending
+        }
+    }
+
+    #[test]
+    fn block_on_should_return_output_of_ready_future() {
+        assert_eq!(block_on(async { 42 }), 42);
+    }
+
+    #[test]
+    fn block_on_should_wait_until_woken() {
+        assert_eq!(block_on(Later(false)), 42);
+    }
+}
@@ -0,0 +1,33 @@
+//! A global scope has commands that are available in every scope a runner runs
+use crate::description::ScopeDescription;
+use crate::scope::Scope;
+use std::fmt::{Debug, Formatter};
+
+/// The global scope of a runner. Only its commands are used, the hooks of the global scope are
+/// not called
+pub(crate) struct GlobalScope {
+    scope: Box<dyn Scope>,
+}
+
+impl GlobalScope {
+    pub(crate) fn new(scope: Box<dyn Scope>) -> Self {
+        GlobalScope { scope }
+    }
+
+    pub(crate) fn commands(&self) -> ScopeDescription {
+        self.scope.commands()
+    }
+
+    pub(crate) fn scope_mut(&mut self) -> &mut dyn Scope {
+        self.scope.as_mut()
+    }
+}
+
+impl Debug for GlobalScope {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> std::fmt::Result {
+        formatter
+            .debug_struct("GlobalScope")
+            .field("commands", &self.commands())
+            .finish()
+    }
+}
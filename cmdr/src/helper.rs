@@ -0,0 +1,333 @@
+//! Rustyline helper that highlights commands and shows usage hints while the user is typing
+use crate::description::ScopeDescription;
+use crate::style::Style;
+use crate::terminal;
+use rustyline::completion::Completer;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::{Hint, Hinter};
+use rustyline::validate::Validator;
+use rustyline::{Context, Helper};
+use std::borrow::Cow;
+use std::iter::once;
+
+/// Rustyline helper that knows about the commands in the current scope
+#[derive(Debug, Default)]
+pub(crate) struct CmdrHelper {
+    commands: Option<ScopeDescription>,
+}
+
+impl CmdrHelper {
+    /// Set the commands of the scope that is currently active, when there are no commands lines
+    /// are not highlighted
+    pub(crate) fn set_commands(&mut self, commands: &ScopeDescription) {
+        self.commands = if commands.all_commands().next().is_some() {
+            Some(commands.clone())
+        } else {
+            None
+        };
+    }
+
+    /// Split a line in the command and the rest of the line
+    fn split_command(line: &str) -> (&str, &str) {
+        let line = line.trim_start();
+        match line.find(' ') {
+            Some(index) => line.split_at(index),
+            None => (line, ""),
+        }
+    }
+
+    /// Compute the hint for a partially entered line
+    fn hint_for(&self, line: &str) -> Option<CommandHint> {
+        let commands = self.commands.as_ref()?;
+        let (command, rest) = Self::split_command(line);
+
+        if command.is_empty() {
+            return None;
+        }
+
+        if rest.is_empty() {
+            if let Some(found) = commands.all_commands().find(|found| found.handles(command)) {
+                return found
+                    .usage()
+                    .map(|usage| CommandHint::new(format!(" {}", usage), None));
+            }
+
+            let mut matching = commands
+                .all_commands()
+                .filter(|found| found.name().starts_with(command));
+
+            match (matching.next(), matching.next()) {
+                (Some(found), None) => {
+                    let completion = found.name()[command.len()..].to_string();
+                    let display = match found.usage() {
+                        Some(usage) => format!("{} {}", completion, usage),
+                        None => completion.clone(),
+                    };
+
+                    Some(CommandHint::new(display, Some(completion)))
+                }
+                _ => None,
+            }
+        } else if rest == " " {
+            commands
+                .all_commands()
+                .find(|found| found.handles(command))
+                .and_then(|found| found.usage())
+                .map(|usage| CommandHint::new(usage.to_string(), None))
+        } else {
+            None
+        }
+    }
+
+    /// Style to highlight an entered command with, None when the command is still being typed
+    fn command_style(&self, command: &str) -> Option<Style> {
+        let commands = self.commands.as_ref()?;
+
+        if commands.all_commands().any(|found| found.handles(command)) {
+            Some(Style::Success)
+        } else if commands
+            .all_commands()
+            .any(|found| found.name().starts_with(command))
+        {
+            None
+        } else {
+            Some(Style::Error)
+        }
+    }
+}
+
+/// Complete the command name that is typed before pos, returns where the command name starts and
+/// the names and aliases it can be completed to. Arguments are not completed
+pub(crate) fn complete_command(
+    commands: &ScopeDescription,
+    line: &str,
+    pos: usize,
+) -> (usize, Vec<String>) {
+    let before = &line[..pos];
+    let start = before.len() - before.trim_start().len();
+    let typed = &before[start..];
+
+    if let Some(index) = typed.find(' ') {
+        // Complete the commands of a mounted scope after its name
+        return match commands.mount(&typed[..index]) {
+            Some(mount) => {
+                let offset = start + index;
+                let (mount_start, candidates) =
+                    complete_command(mount, &line[offset..], pos - offset);
+                (offset + mount_start, candidates)
+            }
+            None => (pos, Vec::new()),
+        };
+    }
+
+    let mut candidates: Vec<String> = commands
+        .all_commands()
+        .flat_map(|command| once(command.name()).chain(command.aliases().map(String::as_str)))
+        .filter(|name| name.starts_with(typed))
+        .map(|name| name.to_string())
+        .collect();
+    candidates.sort();
+    candidates.dedup();
+
+    (start, candidates)
+}
+
+impl Helper for CmdrHelper {}
+
+impl Completer for CmdrHelper {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        match &self.commands {
+            Some(commands) => Ok(complete_command(commands, line, pos)),
+            None => Ok((pos, Vec::new())),
+        }
+    }
+}
+
+impl Validator for CmdrHelper {}
+
+impl Hinter for CmdrHelper {
+    type Hint = CommandHint;
+
+    fn hint(&self, line: &str, pos: usize, _ctx: &Context<'_>) -> Option<CommandHint> {
+        if pos < line.len() {
+            None
+        } else {
+            self.hint_for(line)
+        }
+    }
+}
+
+impl Highlighter for CmdrHelper {
+    fn highlight<'l>(&self, line: &'l str, _pos: usize) -> Cow<'l, str> {
+        let start = line.len() - line.trim_start().len();
+        let (command, rest) = Self::split_command(line);
+
+        match self.command_style(command) {
+            Some(style) if terminal::use_color() && !command.is_empty() => Cow::Owned(format!(
+                "{}{}{}",
+                &line[..start],
+                style.paint(command),
+                rest
+            )),
+            _ => Cow::Borrowed(line),
+        }
+    }
+
+    fn highlight_hint<'h>(&self, hint: &'h str) -> Cow<'h, str> {
+        if terminal::use_color() {
+            Cow::Owned(Style::Dim.paint(hint))
+        } else {
+            Cow::Borrowed(hint)
+        }
+    }
+
+    fn highlight_char(&self, _line: &str, _pos: usize) -> bool {
+        // Highlighting depends on the complete command, so re-highlight after every change
+        true
+    }
+}
+
+/// Hint shown after the cursor, can complete a partially typed command name
+#[derive(Debug, PartialEq)]
+pub(crate) struct CommandHint {
+    display: String,
+    completion: Option<String>,
+}
+
+impl CommandHint {
+    fn new(display: String, completion: Option<String>) -> Self {
+        CommandHint {
+            display,
+            completion,
+        }
+    }
+}
+
+impl Hint for CommandHint {
+    fn display(&self) -> &str {
+        &self.display
+    }
+
+    fn completion(&self) -> Option<&str> {
+        self.completion.as_deref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::description::ScopeCmdDescription;
+
+    fn get_test_helper() -> CmdrHelper {
+        let mut helper = CmdrHelper::default();
+        helper.set_commands(&ScopeDescription::new(
+            None,
+            vec![
+                ScopeCmdDescription::new("greet".to_string(), vec!["hi".to_string()], None)
+                    .with_usage("<name>"),
+                ScopeCmdDescription::new("quit".to_string(), vec![], None),
+                ScopeCmdDescription::new("query".to_string(), vec![], None),
+            ],
+        ));
+        helper
+    }
+
+    #[test]
+    fn should_hint_usage_after_command() {
+        assert_eq!(
+            get_test_helper().hint_for("greet"),
+            Some(CommandHint::new(" <name>".to_string(), None))
+        );
+        assert_eq!(
+            get_test_helper().hint_for("hi "),
+            Some(CommandHint::new("<name>".to_string(), None))
+        );
+    }
+
+    #[test]
+    fn should_complete_unique_command_prefix() {
+        assert_eq!(
+            get_test_helper().hint_for("gr"),
+            Some(CommandHint::new(
+                "eet <name>".to_string(),
+                Some("eet".to_string())
+            ))
+        );
+    }
+
+    #[test]
+    fn should_not_hint_ambiguous_prefix() {
+        assert_eq!(get_test_helper().hint_for("qu"), None);
+    }
+
+    #[test]
+    fn should_not_hint_after_arguments() {
+        assert_eq!(get_test_helper().hint_for("greet Bob"), None);
+    }
+
+    #[test]
+    fn should_complete_command_names_and_aliases() {
+        let helper = get_test_helper();
+        let commands = helper.commands.as_ref().unwrap();
+
+        assert_eq!(
+            complete_command(commands, "  qu", 4),
+            (2, vec!["query".to_string(), "quit".to_string()])
+        );
+        assert_eq!(
+            complete_command(commands, "h", 1),
+            (0, vec!["hi".to_string()])
+        );
+    }
+
+    #[test]
+    fn should_complete_mounted_commands() {
+        let commands = ScopeDescription::new(None, vec![]).with_mount(
+            "db",
+            ScopeDescription::new(
+                None,
+                vec![ScopeCmdDescription::new("query".to_string(), vec![], None)],
+            ),
+        );
+
+        assert_eq!(
+            complete_command(&commands, "db q", 4),
+            (3, vec!["query".to_string()])
+        );
+        assert_eq!(complete_command(&commands, "db query x", 10), (10, vec![]));
+    }
+
+    #[test]
+    fn should_not_complete_arguments() {
+        let helper = get_test_helper();
+
+        assert_eq!(
+            complete_command(helper.commands.as_ref().unwrap(), "greet B", 7),
+            (7, vec![])
+        );
+    }
+
+    #[test]
+    fn should_not_style_without_commands() {
+        let mut helper = get_test_helper();
+        helper.set_commands(&ScopeDescription::new(None, vec![]));
+
+        assert_eq!(helper.command_style("y"), None);
+    }
+
+    #[test]
+    fn should_style_known_and_unknown_commands() {
+        let helper = get_test_helper();
+
+        assert_eq!(helper.command_style("greet"), Some(Style::Success));
+        assert_eq!(helper.command_style("gre"), None);
+        assert_eq!(helper.command_style("grumble"), Some(Style::Error));
+    }
+}
@@ -0,0 +1,330 @@
+//! Interaction lets commands ask the user questions while they are running
+use crate::cancel::CancelToken;
+use crate::context::Context;
+use crate::description::ScopeDescription;
+use crate::line_reader::LineReader;
+use crate::line_writer::LineWriter;
+use crate::messages::{DefaultMessages, Messages};
+use crate::record::Record;
+use crate::result::{Error, Result};
+use crate::style::Style;
+use std::any::Any;
+
+/// Handle that is passed to commands to interact with the user. It reads answers from the same
+/// LineReader the runner reads commands from, so it also works with scripted readers. Output can
+/// be written to the interaction like to any other LineWriter.
+pub struct Interaction<'a> {
+    reader: &'a mut dyn LineReader,
+    writer: &'a mut dyn LineWriter,
+    cancel: CancelToken,
+    context: Option<&'a mut dyn Any>,
+    messages: &'a dyn Messages,
+    help_sections: &'a [(String, ScopeDescription)],
+}
+
+impl<'a> Interaction<'a> {
+    /// Construct an interaction that reads from reader and writes to writer
+    pub fn new(reader: &'a mut dyn LineReader, writer: &'a mut dyn LineWriter) -> Self {
+        Interaction {
+            reader,
+            writer,
+            cancel: CancelToken::new(),
+            context: None,
+            messages: &DefaultMessages,
+            help_sections: &[],
+        }
+    }
+
+    /// Show messages instead of the default English texts, runners pass their messages
+    pub fn with_messages(mut self, messages: &'a dyn Messages) -> Self {
+        self.messages = messages;
+        self
+    }
+
+    /// The messages of the runner, used to show texts built into cmdr
+    pub fn messages(&self) -> &'a dyn Messages {
+        self.messages
+    }
+
+    /// Use token to signal the command that it should stop
+    pub fn with_cancel_token(mut self, token: CancelToken) -> Self {
+        self.cancel = token;
+        self
+    }
+
+    /// Pass the context of the runner, a `Context<T>`, to the command
+    pub fn with_context(mut self, context: &'a mut dyn Any) -> Self {
+        self.context = Some(context);
+        self
+    }
+
+    /// Sections with commands the runner adds to every scope, like the commands of the global
+    /// scope. Help lists these after the commands of the scope
+    pub fn with_help_sections(mut self, sections: &'a [(String, ScopeDescription)]) -> Self {
+        self.help_sections = sections;
+        self
+    }
+
+    /// The sections with commands the runner adds to every scope, as headers and commands
+    pub fn help_sections(&self) -> &'a [(String, ScopeDescription)] {
+        self.help_sections
+    }
+
+    /// The context of the runner, an error when the runner has no context of type `T`
+    pub fn context<T: Any>(&mut self) -> Result<&mut Context<T>> {
+        Context::downcast(&mut self.context)
+    }
+
+    /// Take the context out of the interaction so it can be borrowed next to the interaction,
+    /// put it back with `set_context`
+    pub fn take_context(&mut self) -> Option<&'a mut dyn Any> {
+        self.context.take()
+    }
+
+    /// Put back a context taken with `take_context`
+    pub fn set_context(&mut self, context: Option<&'a mut dyn Any>) {
+        self.context = context;
+    }
+
+    /// The token that is cancelled when the user presses Ctrl-C while the command runs, can be
+    /// cloned to pass it to other threads
+    pub fn cancel_token(&self) -> &CancelToken {
+        &self.cancel
+    }
+
+    /// Return `Error::Cancelled` when the user pressed Ctrl-C, long running commands should call
+    /// this regularly
+    pub fn check_cancelled(&self) -> Result<()> {
+        self.cancel.check()
+    }
+
+    /// Ctrl-C while answering a question cancels the command instead of quitting
+    fn cancel_on_ctrl_c(answer: Result<String>) -> Result<String> {
+        match answer {
+            Err(Error::CtrlC) => Err(Error::Cancelled),
+            answer => answer,
+        }
+    }
+
+    /// Read a line from the user, answers are not highlighted as commands
+    fn read_answer(&mut self, prompt: &str) -> Result<String> {
+        self.writer.flush();
+        self.reader
+            .set_commands(&ScopeDescription::new(None, Vec::new()));
+
+        let answer = Self::cancel_on_ctrl_c(self.reader.read_line(prompt))?;
+        Ok(answer.trim_end_matches(&['\r', '\n'][..]).to_string())
+    }
+
+    /// Ask a yes/no question, returns false when the user just presses enter
+    pub fn confirm(&mut self, question: &str) -> Result<bool> {
+        let messages = self.messages;
+        let prompt = messages.confirm_prompt(question);
+
+        loop {
+            match messages.confirm_answer(&self.read_answer(&prompt)?) {
+                Some(answer) => return Ok(answer),
+                None => self
+                    .writer
+                    .write_styled_line(Style::Warning, &messages.confirm_invalid()),
+            }
+        }
+    }
+
+    /// Ask the user to enter some text
+    pub fn input(&mut self, prompt: &str) -> Result<String> {
+        self.read_answer(prompt)
+    }
+
+    /// Ask the user to enter some text and validate it. The validation function returns an error
+    /// message when the text is not valid, the message is shown and the user is asked again.
+    pub fn input_valid<F>(&mut self, prompt: &str, validate: F) -> Result<String>
+    where
+        F: Fn(&str) -> std::result::Result<(), String>,
+    {
+        loop {
+            let answer = self.read_answer(prompt)?;
+
+            match validate(&answer) {
+                Ok(()) => return Ok(answer),
+                Err(message) => self.writer.write_styled_line(Style::Warning, &message),
+            }
+        }
+    }
+
+    /// Ask the user to enter a password, the password is not shown while typing when the reader
+    /// supports this
+    pub fn password(&mut self, prompt: &str) -> Result<String> {
+        self.writer.flush();
+
+        let password = Self::cancel_on_ctrl_c(self.reader.read_password(prompt))?;
+        Ok(password.trim_end_matches(&['\r', '\n'][..]).to_string())
+    }
+
+    /// Let the user choose from a numbered list of options, returns the index of the chosen
+    /// option or an error when there are no options to choose from
+    pub fn select(&mut self, prompt: &str, options: &[&str]) -> Result<usize> {
+        if options.is_empty() {
+            return Err(Error::custom(self.messages.select_empty()));
+        }
+
+        for (number, option) in options.iter().enumerate() {
+            self.writer
+                .write_line(&format!("{}) {}", number + 1, option));
+        }
+
+        let messages = self.messages;
+        let prompt = messages.select_prompt(prompt, options.len());
+
+        loop {
+            match self.read_answer(&prompt)?.trim().parse::<usize>() {
+                Ok(number) if number >= 1 && number <= options.len() => return Ok(number - 1),
+                _ => self
+                    .writer
+                    .write_styled_line(Style::Warning, &messages.select_invalid(options.len())),
+            }
+        }
+    }
+}
+
+impl LineWriter for Interaction<'_> {
+    fn write_line(&mut self, line: &str) {
+        self.writer.write_line(line)
+    }
+
+    fn write(&mut self, line: &str) {
+        self.writer.write(line)
+    }
+
+    fn flush(&mut self) {
+        self.writer.flush()
+    }
+
+    fn width(&self) -> usize {
+        self.writer.width()
+    }
+
+    fn supports_style(&self) -> bool {
+        self.writer.supports_style()
+    }
+
+    fn is_terminal(&self) -> bool {
+        self.writer.is_terminal()
+    }
+
+    fn write_styled(&mut self, style: Style, text: &str) {
+        self.writer.write_styled(style, text)
+    }
+
+    fn write_record(&mut self, record: &Record) {
+        self.writer.write_record(record)
+    }
+
+    fn write_records(&mut self, records: &[Record]) {
+        self.writer.write_records(records)
+    }
+}
+
+impl std::fmt::Debug for Interaction<'_> {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        formatter.debug_struct("Interaction").finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::line_reader::FileLineReader;
+
+    /// Run an interaction with scripted input and return the result and the output
+    fn interact<T>(
+        input: &str,
+        action: impl FnOnce(&mut Interaction) -> Result<T>,
+    ) -> (Result<T>, String) {
+        let mut reader = FileLineReader::new(input.as_bytes());
+        let mut output = String::new();
+
+        let result = action(&mut Interaction::new(&mut reader, &mut output));
+        (result, output)
+    }
+
+    #[test]
+    fn confirm_should_accept_yes() {
+        let (result, _) = interact("yes\n", |interaction| interaction.confirm("Sure?"));
+        assert_eq!(result, Ok(true));
+    }
+
+    #[test]
+    fn confirm_should_default_to_no() {
+        let (result, _) = interact("\n", |interaction| interaction.confirm("Sure?"));
+        assert_eq!(result, Ok(false));
+    }
+
+    #[test]
+    fn confirm_should_ask_again_on_invalid_answer() {
+        let (result, output) = interact("maybe\ny\n", |interaction| interaction.confirm("Sure?"));
+
+        assert_eq!(result, Ok(true));
+        assert_eq!(output, "Please answer y or n\n");
+    }
+
+    #[test]
+    fn input_should_return_line_without_line_ending() {
+        let (result, _) = interact("Bob\r\n", |interaction| interaction.input("Name?"));
+        assert_eq!(result, Ok("Bob".to_string()));
+    }
+
+    #[test]
+    fn input_valid_should_ask_until_valid() {
+        let (result, output) = interact("\nBob\n", |interaction| {
+            interaction.input_valid("Name?", |name| {
+                if name.is_empty() {
+                    Err("Name can not be empty".to_string())
+                } else {
+                    Ok(())
+                }
+            })
+        });
+
+        assert_eq!(result, Ok("Bob".to_string()));
+        assert_eq!(output, "Name can not be empty\n");
+    }
+
+    #[test]
+    fn select_should_return_index_of_chosen_option() {
+        let (result, output) = interact("5\n2\n", |interaction| {
+            interaction.select("Color?", &["red", "green"])
+        });
+
+        assert_eq!(result, Ok(1));
+        assert_eq!(
+            output,
+            "1) red\n2) green\nPlease enter a number from 1 to 2\n"
+        );
+    }
+
+    #[test]
+    fn select_should_fail_without_options() {
+        let (result, output) = interact("1\n", |interaction| interaction.select("Color?", &[]));
+
+        assert_eq!(result, Err(Error::custom("Nothing to select from")));
+        assert_eq!(output, "");
+    }
+
+    #[test]
+    fn interaction_should_pass_on_end_of_input() {
+        let (result, _) = interact("", |interaction| interaction.confirm("Sure?"));
+        assert_eq!(result, Err(Error::CtrlD));
+    }
+
+    #[test]
+    fn check_cancelled_should_fail_after_cancel() {
+        let (result, _) = interact("", |interaction| {
+            interaction.check_cancelled()?;
+            interaction.cancel_token().cancel();
+            interaction.check_cancelled()
+        });
+
+        assert_eq!(result, Err(Error::Cancelled));
+    }
+}
@@ -0,0 +1,392 @@
+//! Background jobs that keep running on their own thread while the user enters other commands
+use crate::cancel::CancelToken;
+use crate::description::{ScopeCmdDescription, ScopeDescription};
+use crate::line::Line;
+use crate::line_writer::LineWriter;
+use crate::messages::{DefaultMessages, Messages};
+use crate::result::{Action, CommandResult, Error, Result};
+use crate::style::Style;
+use std::fmt::{Debug, Formatter};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+/// Work that runs on a background thread, started by returning `Action::Background` from a
+/// command. The work gets a CancelToken that is cancelled by the kill command, the text it returns
+/// is shown when the job is done.
+pub struct Job {
+    name: String,
+    work: Box<dyn FnOnce(CancelToken) -> Result<String> + Send>,
+}
+
+impl Job {
+    /// Construct a job, the name is shown in the list of jobs
+    pub fn new<F>(name: &str, work: F) -> Self
+    where
+        F: FnOnce(CancelToken) -> Result<String> + Send + 'static,
+    {
+        Job {
+            name: name.to_string(),
+            work: Box::new(work),
+        }
+    }
+
+    /// The name of the job
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl Debug for Job {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> std::fmt::Result {
+        formatter
+            .debug_struct("Job")
+            .field("name", &self.name)
+            .finish()
+    }
+}
+
+/// Result of a job thread, Err when the job panicked
+type JobResult = thread::Result<Result<String>>;
+
+/// A job that has been started and did not report back yet
+#[derive(Debug)]
+struct RunningJob {
+    id: usize,
+    name: String,
+    cancel: CancelToken,
+}
+
+/// Keeps track of the background jobs of a runner and implements the jobs, wait and kill commands
+#[derive(Debug)]
+pub(crate) struct Jobs {
+    running: Vec<RunningJob>,
+    next_id: usize,
+    catch_interrupt: bool,
+    messages: Arc<dyn Messages>,
+    sender: Sender<(usize, JobResult)>,
+    receiver: Receiver<(usize, JobResult)>,
+}
+
+impl Jobs {
+    pub(crate) fn new() -> Self {
+        let (sender, receiver) = channel();
+
+        Jobs {
+            running: Vec::new(),
+            next_id: 1,
+            catch_interrupt: true,
+            messages: Arc::new(DefaultMessages),
+            sender,
+            receiver,
+        }
+    }
+
+    /// Set whether Ctrl-C in the terminal stops waiting for jobs
+    pub(crate) fn set_catch_interrupt(&mut self, catch: bool) {
+        self.catch_interrupt = catch;
+    }
+
+    /// Set the messages used for job notices and the help of the job commands
+    pub(crate) fn set_messages(&mut self, messages: Arc<dyn Messages>) {
+        self.messages = messages;
+    }
+
+    /// Check if the job commands are available, they are turned on when the first job starts so
+    /// they do not take command names from applications that do not use jobs
+    pub(crate) fn enabled(&self) -> bool {
+        self.next_id > 1
+    }
+
+    /// The built-in job commands, none until the first job was started
+    pub(crate) fn commands(&self) -> ScopeDescription {
+        if !self.enabled() {
+            return ScopeDescription::new(None, vec![]);
+        }
+        let messages = &self.messages;
+
+        ScopeDescription::new(
+            None,
+            vec![
+                ScopeCmdDescription::new("jobs".to_string(), vec![], Some(messages.jobs_help())),
+                ScopeCmdDescription::new(
+                    "wait".to_string(),
+                    vec!["fg".to_string()],
+                    Some(messages.wait_help()),
+                )
+                .with_usage("[job]"),
+                ScopeCmdDescription::new("kill".to_string(), vec![], Some(messages.kill_help()))
+                    .with_usage("<job>"),
+            ],
+        )
+    }
+
+    /// Start a job on a new thread
+    pub(crate) fn start(&mut self, job: Job, writer: &mut dyn LineWriter) {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        let cancel = CancelToken::new();
+        let job_cancel = cancel.clone();
+        let sender = self.sender.clone();
+        let work = job.work;
+
+        thread::spawn(move || {
+            let result = catch_unwind(AssertUnwindSafe(move || work(job_cancel)));
+            sender.send((id, result)).ok();
+        });
+
+        writer.write_line(&format!("[{}] {}", id, job.name));
+        self.running.push(RunningJob {
+            id,
+            name: job.name,
+            cancel,
+        });
+    }
+
+    /// Write a notice for every job that finished since the last call
+    pub(crate) fn report_finished(&mut self, writer: &mut dyn LineWriter) {
+        while let Ok((id, result)) = self.receiver.try_recv() {
+            self.finish(id, result, writer);
+        }
+    }
+
+    /// Run a built-in job command for line, returns None when line is not a job command or no job
+    /// was started yet
+    pub(crate) fn run_line(
+        &mut self,
+        line: &Line,
+        writer: &mut dyn LineWriter,
+    ) -> Option<CommandResult> {
+        let command = self.commands().command_for_line(line)?.name().to_string();
+
+        Some(match command.as_ref() {
+            "jobs" => self.list(writer),
+            "wait" => self.wait(&line.args, writer),
+            _ => self.kill(&line.args, writer),
+        })
+    }
+
+    fn list(&self, writer: &mut dyn LineWriter) -> CommandResult {
+        if self.running.is_empty() {
+            writer.write_line(&self.messages.no_jobs());
+        } else {
+            let rows: Vec<Vec<String>> = self
+                .running
+                .iter()
+                .map(|job| vec![job.id.to_string(), job.name.clone()])
+                .collect();
+            writer.write_table(&["id", "name"], &rows);
+        }
+        Ok(Action::Done)
+    }
+
+    fn wait(&mut self, args: &[String], writer: &mut dyn LineWriter) -> CommandResult {
+        let id = match args.first() {
+            Some(arg) => match self.find(arg) {
+                Some(id) => Some(id),
+                None => return self.no_such_job(arg, writer),
+            },
+            None => None,
+        };
+
+        // Waiting can be cancelled with Ctrl-C, the jobs keep running
+        let cancel = CancelToken::new();
+        let _interrupt = if self.catch_interrupt {
+            Some(cancel.cancel_on_interrupt())
+        } else {
+            None
+        };
+
+        while id.map_or(!self.running.is_empty(), |id| self.is_running(id)) {
+            cancel.check()?;
+
+            match self.receiver.recv_timeout(Duration::from_millis(100)) {
+                Ok((id, result)) => self.finish(id, result, writer),
+                Err(RecvTimeoutError::Timeout) => (),
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+        }
+        Ok(Action::Done)
+    }
+
+    fn kill(&mut self, args: &[String], writer: &mut dyn LineWriter) -> CommandResult {
+        let arg = args
+            .first()
+            .ok_or_else(|| Error::InvalidNumberOfArguments("kill".to_string()))?;
+
+        match self.find(arg) {
+            Some(id) => {
+                for job in self.running.iter().filter(|job| job.id == id) {
+                    job.cancel.cancel();
+                }
+                Ok(Action::Done)
+            }
+            None => self.no_such_job(arg, writer),
+        }
+    }
+
+    /// Find the id of a running job
+    fn find(&self, arg: &str) -> Option<usize> {
+        let id = arg.trim_start_matches('%').parse().ok()?;
+        if self.is_running(id) {
+            Some(id)
+        } else {
+            None
+        }
+    }
+
+    fn is_running(&self, id: usize) -> bool {
+        self.running.iter().any(|job| job.id == id)
+    }
+
+    fn no_such_job(&self, arg: &str, writer: &mut dyn LineWriter) -> CommandResult {
+        writer.write_styled_line(Style::Warning, &self.messages.no_such_job(arg));
+        Ok(Action::Done)
+    }
+
+    /// Remove a job that reported back and write what happened to it
+    fn finish(&mut self, id: usize, result: JobResult, writer: &mut dyn LineWriter) {
+        let name = match self.running.iter().position(|job| job.id == id) {
+            Some(index) => self.running.remove(index).name,
+            None => return,
+        };
+
+        let messages = self.messages.clone();
+
+        match result {
+            Ok(Ok(output)) => {
+                writer.write_styled_line(Style::Success, &messages.job_done(id, &name));
+                if !output.is_empty() {
+                    writer.write_line(output.trim_end());
+                }
+            }
+            Ok(Err(Error::Cancelled)) => {
+                writer.write_styled_line(Style::Warning, &messages.job_killed(id, &name))
+            }
+            Ok(Err(error)) => writer.write_styled_line(
+                Style::Error,
+                &messages.job_failed(id, &name, &messages.error(&error)),
+            ),
+            Err(_) => {
+                writer.write_styled_line(Style::Error, &messages.job_failed(id, &name, "panicked"))
+            }
+        }
+    }
+}
+
+impl Drop for Jobs {
+    fn drop(&mut self) {
+        // Ask jobs that are still running to stop when the runner goes away
+        for job in &self.running {
+            job.cancel.cancel();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(jobs: &mut Jobs, line: &str, output: &mut String) -> Option<CommandResult> {
+        jobs.run_line(&Line::try_parse(line).unwrap(), output)
+    }
+
+    /// Jobs that ran a job that is done, so the job commands are available
+    fn enabled_jobs() -> Jobs {
+        let mut jobs = Jobs::new();
+        jobs.start(Job::new("first", |_| Ok(String::new())), &mut String::new());
+        run(&mut jobs, "wait", &mut String::new());
+        jobs
+    }
+
+    #[test]
+    fn job_commands_should_be_off_until_first_job() {
+        let mut jobs = Jobs::new();
+
+        assert!(jobs.commands().all_commands().next().is_none());
+        assert!(run(&mut jobs, "jobs", &mut String::new()).is_none());
+
+        jobs.start(Job::new("first", |_| Ok(String::new())), &mut String::new());
+
+        assert!(run(&mut jobs, "jobs", &mut String::new()).is_some());
+    }
+
+    #[test]
+    fn wait_should_report_finished_job() {
+        let mut jobs = Jobs::new();
+        let mut output = String::new();
+
+        jobs.start(Job::new("answer", |_| Ok("42".to_string())), &mut output);
+        run(&mut jobs, "wait 1", &mut output);
+
+        assert_eq!(output, "[1] answer\n[1] Done    answer\n42\n");
+    }
+
+    #[test]
+    fn kill_should_cancel_job() {
+        let mut jobs = Jobs::new();
+        let mut output = String::new();
+
+        jobs.start(
+            Job::new("forever", |cancel| loop {
+                cancel.check()?;
+                thread::sleep(Duration::from_millis(1));
+            }),
+            &mut output,
+        );
+        run(&mut jobs, "kill %1", &mut output);
+        run(&mut jobs, "wait", &mut output);
+
+        assert_eq!(output, "[1] forever\n[1] Killed  forever\n");
+    }
+
+    #[test]
+    fn jobs_should_list_running_jobs() {
+        let mut jobs = enabled_jobs();
+        let mut output = String::new();
+
+        run(&mut jobs, "jobs", &mut output);
+
+        assert_eq!(output, "No jobs running\n");
+    }
+
+    #[test]
+    fn should_report_unknown_job() {
+        let mut jobs = enabled_jobs();
+        let mut output = String::new();
+
+        run(&mut jobs, "kill 7", &mut output);
+
+        assert_eq!(output, "No such job: 7\n");
+    }
+
+    #[derive(Debug)]
+    struct DutchMessages;
+
+    impl Messages for DutchMessages {
+        fn no_such_job(&self, job: &str) -> String {
+            format!("Taak bestaat niet: {}", job)
+        }
+    }
+
+    #[test]
+    fn should_report_with_messages() {
+        let mut jobs = enabled_jobs();
+        jobs.set_messages(Arc::new(DutchMessages));
+        let mut output = String::new();
+
+        run(&mut jobs, "wait 7", &mut output);
+
+        assert_eq!(output, "Taak bestaat niet: 7\n");
+    }
+
+    #[test]
+    fn should_ignore_other_commands() {
+        let mut jobs = Jobs::new();
+
+        assert!(run(&mut jobs, "greet", &mut String::new()).is_none());
+    }
+}
@@ -0,0 +1,488 @@
+//! Runs scopes for other programs, requests and responses are JSON objects, one per line
+use crate::description::ScopeDescription;
+use crate::format::DEFAULT_WIDTH;
+use crate::future::block_on;
+use crate::line::Line;
+use crate::line_reader::{LineReader, NoLineReader};
+use crate::line_writer::LineWriter;
+use crate::messages::Messages;
+use crate::middleware::Middleware;
+use crate::record::{Record, Value};
+use crate::result::{Action, CommandResult, Error};
+use crate::run_loop::{BlockingInput, RunLoop};
+use crate::scope::Scope;
+use serde_json::{json, Map, Number, Value as Json};
+use std::any::Any;
+use std::mem;
+use std::sync::Arc;
+
+/// Runs commands from JSON requests so scopes can be driven by other programs. Every line read
+/// from the reader is a request, every response is written as a single line of JSON.
+///
+/// A command request contains the command and its arguments:
+///
+/// ```text
+/// {"command": "greet", "args": ["Bob"], "id": 1}
+/// ```
+///
+/// The response contains the captured output, records written by the command, the resulting
+/// action and the error if the command failed. The optional id is copied to the response:
+///
+/// ```text
+/// {"id": 1, "output": "Hello Bob\n", "records": [], "action": "done", "error": null}
+/// ```
+///
+/// Commands are found and run like the `Runner` does, with the global scope, the context and the
+/// middleware of the runner. A fatal error is returned in a response and then stops the runner.
+///
+/// The request `{"request": "describe"}` returns the description of the current scope.
+#[derive(Debug)]
+pub struct JsonRunner<R: LineReader, W: LineWriter> {
+    reader: R,
+    writer: W,
+    /// Runs commands, its writer captures their output for the response
+    core: RunLoop<BlockingInput<NoLineReader>, CaptureWriter>,
+}
+
+impl<R: LineReader, W: LineWriter> JsonRunner<R, W> {
+    /// Create a runner that reads requests from `reader` and writes responses to `writer`
+    pub fn new(reader: R, writer: W) -> Self {
+        let mut core = RunLoop::new(
+            BlockingInput {
+                reader: NoLineReader,
+            },
+            CaptureWriter::default(),
+        );
+        core.set_catch_interrupt(false);
+
+        JsonRunner {
+            reader,
+            writer,
+            core,
+        }
+    }
+
+    /// Use messages instead of the default English texts built into cmdr in the output of
+    /// commands
+    pub fn with_messages<M: Messages + 'static>(mut self, messages: M) -> Self {
+        self.core.set_messages(Arc::new(messages));
+        self
+    }
+
+    /// Set the context that commands in all scopes can borrow with a `&mut Context<T>` argument
+    pub fn with_context<T: Any>(mut self, context: T) -> Self {
+        self.core.set_context(context);
+        self
+    }
+
+    /// Set a scope with commands that are available in every scope, like `Runner::with_global_scope`
+    pub fn with_global_scope<S: Scope + 'static>(mut self, scope: S) -> Self {
+        self.core.set_global_scope(Box::new(scope));
+        self
+    }
+
+    /// Add middleware that runs around every command, like `Runner::with_middleware`
+    pub fn with_middleware<M: Middleware + 'static>(mut self, middleware: M) -> Self {
+        self.core.middleware.push(Box::new(middleware));
+        self
+    }
+
+    /// Handle requests until the reader has no more requests or a command quits
+    pub fn run<S: Scope>(&mut self, scope: &mut S) -> CommandResult {
+        let mut root: Option<&mut dyn Scope> = Some(scope);
+        let mut sub_scopes: Vec<Box<dyn Scope>> = Vec::new();
+
+        if let Some(root) = root.as_deref_mut() {
+            root.before_loop();
+        }
+
+        loop {
+            let request = match self.reader.read_line("") {
+                Ok(request) => request,
+                Err(Error::CtrlD) => break,
+                Err(error) => return Err(error),
+            };
+
+            if request.trim().is_empty() {
+                continue;
+            }
+
+            let current: &mut dyn Scope = match sub_scopes.last_mut() {
+                Some(scope) => scope.as_mut(),
+                None => match root.as_deref_mut() {
+                    Some(root) => root,
+                    None => break,
+                },
+            };
+
+            let (response, result) = self.handle(current, &request);
+            self.writer.write_line(&response.to_string());
+            self.writer.flush();
+
+            match result {
+                Some(Ok(Action::SubScope(mut scope))) => {
+                    scope.before_loop();
+                    sub_scopes.push(scope);
+                }
+                Some(Ok(Action::NewScope(mut scope))) => {
+                    end_scopes(root.take(), &mut sub_scopes);
+                    scope.before_loop();
+                    sub_scopes.push(scope);
+                }
+                Some(Ok(Action::Exit)) => match sub_scopes.pop() {
+                    Some(mut scope) => scope.after_loop(),
+                    None => {
+                        end_scopes(root.take(), &mut sub_scopes);
+                        return Ok(Action::Done);
+                    }
+                },
+                Some(Ok(Action::Quit)) => {
+                    end_scopes(root.take(), &mut sub_scopes);
+                    return Ok(Action::Quit);
+                }
+                Some(Err(Error::Fatal(code))) => {
+                    end_scopes(root.take(), &mut sub_scopes);
+                    return Err(Error::Fatal(code));
+                }
+                _ => (),
+            }
+        }
+
+        end_scopes(root.take(), &mut sub_scopes);
+        Ok(Action::Done)
+    }
+
+    /// Handle a single request, returns the response and the result of the command
+    fn handle(&mut self, scope: &mut dyn Scope, request: &str) -> (Json, Option<CommandResult>) {
+        self.core.jobs.report_finished(&mut self.core.writer);
+
+        let request: Json = match serde_json::from_str(request) {
+            Ok(request) => request,
+            Err(error) => return (error_response(&format!("Invalid request: {}", error)), None),
+        };
+
+        let mut response = match request
+            .get("request")
+            .and_then(Json::as_str)
+            .unwrap_or("command")
+        {
+            "command" => match parse_line(&request) {
+                Some(line) => {
+                    let result = self.run_command(scope, line);
+                    let capture = mem::take(&mut self.core.writer);
+                    return (
+                        with_id(
+                            command_response(&result, capture, &*self.core.messages),
+                            &request,
+                        ),
+                        Some(result),
+                    );
+                }
+                None => error_response("Invalid request: command is missing"),
+            },
+            "describe" => json!({ "description": self.describe(scope) }),
+            other => error_response(&format!("Unknown request: {}", other)),
+        };
+
+        if let Some(id) = request.get("id") {
+            response["id"] = id.clone();
+        }
+        (response, None)
+    }
+
+    /// Run a command like the Runner does, output is captured instead of shown
+    fn run_command(&mut self, scope: &mut dyn Scope, line: Line) -> CommandResult {
+        let line = scope.before_command(line);
+        let result = block_on(self.core.run_line(scope, &line));
+
+        match scope.after_command(&line, result) {
+            Err(error) => scope.handle_error(error),
+            result => result,
+        }
+    }
+
+    /// The commands of scope with the sections of the runner, like help shows them
+    fn describe(&self, scope: &dyn Scope) -> ScopeDescription {
+        self.core
+            .help_sections()
+            .into_iter()
+            .fold(scope.commands(), |description, (header, section)| {
+                description.with_section(&header, section)
+            })
+    }
+}
+
+/// Call after_loop on all scopes that are still running, innermost first
+fn end_scopes(root: Option<&mut dyn Scope>, sub_scopes: &mut Vec<Box<dyn Scope>>) {
+    while let Some(mut scope) = sub_scopes.pop() {
+        scope.after_loop();
+    }
+    if let Some(root) = root {
+        root.after_loop();
+    }
+}
+
+/// Collects command output so it can be returned in a response
+#[derive(Debug, Default)]
+struct CaptureWriter {
+    output: String,
+    records: Vec<Record>,
+}
+
+impl LineWriter for CaptureWriter {
+    fn write(&mut self, line: &str) {
+        self.output.push_str(line)
+    }
+
+    fn width(&self) -> usize {
+        DEFAULT_WIDTH
+    }
+
+    fn write_record(&mut self, record: &Record) {
+        self.records.push(record.clone())
+    }
+
+    fn write_records(&mut self, records: &[Record]) {
+        self.records.extend_from_slice(records)
+    }
+}
+
+/// Read the command and arguments from a command request
+fn parse_line(request: &Json) -> Option<Line> {
+    let command = request.get("command")?.as_str()?.to_string();
+    let args = match request.get("args") {
+        Some(Json::Array(args)) => args
+            .iter()
+            .map(|arg| match arg {
+                Json::String(arg) => arg.clone(),
+                arg => arg.to_string(),
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    Some(Line { command, args })
+}
+
+fn with_id(mut response: Json, request: &Json) -> Json {
+    if let Some(id) = request.get("id") {
+        response["id"] = id.clone();
+    }
+    response
+}
+
+fn error_response(message: &str) -> Json {
+    json!({ "error": message })
+}
+
+fn command_response(
+    result: &CommandResult,
+    capture: CaptureWriter,
+    messages: &dyn Messages,
+) -> Json {
+    let (action, error) = match result {
+        Ok(action) => (Json::from(action_name(action)), Json::Null),
+        Err(error) => (Json::Null, Json::from(error_message(error, messages))),
+    };
+
+    json!({
+        "output": capture.output,
+        "records": capture.records.iter().map(record_json).collect::<Vec<_>>(),
+        "action": action,
+        "error": error,
+    })
+}
+
+/// The message for an error like the Runner shows it, application errors include their causes
+fn error_message(error: &Error, messages: &dyn Messages) -> String {
+    match error {
+        Error::Custom(_) => error.report(messages),
+        error => messages.error(error),
+    }
+}
+
+fn action_name(action: &Action) -> &'static str {
+    match action {
+        Action::Done => "done",
+        Action::NewScope(_) => "new_scope",
+        Action::SubScope(_) => "sub_scope",
+        Action::Background(_) => "background",
+        Action::Exit => "exit",
+        Action::Quit => "quit",
+    }
+}
+
+fn record_json(record: &Record) -> Json {
+    let mut object = Map::new();
+
+    for (name, value) in record.fields() {
+        let value = match value {
+            Value::Text(text) => Json::from(text.as_str()),
+            Value::Integer(number) => Json::from(*number),
+            Value::Float(number) => Number::from_f64(*number).map_or(Json::Null, Json::Number),
+            Value::Bool(value) => Json::from(*value),
+            Value::Null => Json::Null,
+        };
+        object.insert(name.to_string(), value);
+    }
+
+    Json::Object(object)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::description::ScopeCmdDescription;
+    use crate::dynamic_scope::DynamicScope;
+    use crate::interaction::Interaction;
+    use crate::line_reader::FileLineReader;
+
+    struct TestScope {}
+
+    impl Scope for TestScope {
+        fn commands(&self) -> ScopeDescription {
+            ScopeDescription::new(
+                None,
+                vec![
+                    ScopeCmdDescription::new("greet".to_string(), vec![], None),
+                    ScopeCmdDescription::new("user".to_string(), vec![], None),
+                    ScopeCmdDescription::new("fail".to_string(), vec![], None),
+                    ScopeCmdDescription::new("crash".to_string(), vec![], None),
+                    ScopeCmdDescription::new("quit".to_string(), vec![], None),
+                ],
+            )
+        }
+
+        fn run_command(
+            &mut self,
+            command: &ScopeCmdDescription,
+            args: &[String],
+            interaction: &mut Interaction,
+        ) -> CommandResult {
+            match command.name() {
+                "greet" => {
+                    interaction.write_line(&format!("Hello {}", args.join(" ")));
+                    Ok(Action::Done)
+                }
+                "user" => {
+                    interaction.write_record(&Record::new().field("name", "Bob").field("age", 42));
+                    Ok(Action::Done)
+                }
+                "fail" => Err(Error::custom("Disk full")),
+                "crash" => Err(Error::Fatal(3)),
+                "quit" => Ok(Action::Quit),
+                _ => Err(Error::InvalidCommand(command.name().to_string())),
+            }
+        }
+    }
+
+    /// Run requests and return the result and the parsed responses
+    fn run_result(requests: &str) -> (CommandResult, Vec<Json>) {
+        let mut output = String::new();
+        let result = JsonRunner::new(FileLineReader::new(requests.as_bytes()), &mut output)
+            .run(&mut TestScope {});
+
+        let responses = output
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        (result, responses)
+    }
+
+    /// Run requests and return the parsed responses
+    fn run(requests: &str) -> Vec<Json> {
+        let (result, responses) = run_result(requests);
+        result.unwrap();
+        responses
+    }
+
+    #[test]
+    fn should_run_command_and_capture_output() {
+        let responses = run("{\"command\": \"greet\", \"args\": [\"Bob\"], \"id\": 7}\n");
+
+        assert_eq!(
+            responses,
+            vec![json!({
+                "id": 7,
+                "output": "Hello Bob\n",
+                "records": [],
+                "action": "done",
+                "error": null,
+            })]
+        );
+    }
+
+    #[test]
+    fn should_return_records_as_json() {
+        let responses = run("{\"command\": \"user\"}\n");
+
+        assert_eq!(responses[0]["records"], json!([{"name": "Bob", "age": 42}]));
+    }
+
+    #[test]
+    fn should_return_errors() {
+        let responses = run("{\"command\": \"shout\"}\n{\"command\": \"fail\"}\nnot json\n");
+
+        assert_eq!(responses[0]["error"], json!("Unknown command: shout"));
+        assert_eq!(responses[1]["error"], json!("Error: Disk full"));
+        assert!(responses[2]["error"]
+            .as_str()
+            .unwrap()
+            .starts_with("Invalid request"));
+    }
+
+    #[test]
+    fn should_describe_scope() {
+        let responses = run("{\"request\": \"describe\"}\n");
+
+        assert_eq!(responses[0]["description"]["commands"][0]["name"], "greet");
+    }
+
+    #[test]
+    fn should_stop_on_quit() {
+        let responses = run("{\"command\": \"quit\"}\n{\"command\": \"greet\"}\n");
+
+        assert_eq!(responses.len(), 1);
+        assert_eq!(responses[0]["action"], "quit");
+    }
+
+    #[test]
+    fn should_run_global_commands_and_describe_them() {
+        let mut output = String::new();
+        JsonRunner::new(
+            FileLineReader::new(
+                "{\"command\": \"version\"}\n{\"request\": \"describe\"}\n".as_bytes(),
+            ),
+            &mut output,
+        )
+        .with_global_scope(DynamicScope::new().with_command(
+            ScopeCmdDescription::new("version".to_string(), vec![], None),
+            |_, interaction| {
+                interaction.write_line("1.0");
+                Ok(Action::Done)
+            },
+        ))
+        .run(&mut TestScope {})
+        .unwrap();
+        let responses: Vec<Json> = output
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+
+        assert_eq!(responses[0]["output"], "1.0\n");
+        assert_eq!(
+            responses[1]["description"]["sections"][0][1]["commands"][1]["name"],
+            "version"
+        );
+    }
+
+    #[test]
+    fn should_stop_on_fatal_error() {
+        let (result, responses) =
+            run_result("{\"command\": \"crash\"}\n{\"command\": \"greet\"}\n");
+
+        assert_eq!(result.unwrap_err(), Error::Fatal(3));
+        assert_eq!(responses.len(), 1);
+        assert!(responses[0]["error"].is_string());
+    }
+}
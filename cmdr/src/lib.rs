@@ -6,7 +6,21 @@
 //! - Command line parsing
 //! - Command history
 //! - Help functions and discoverability
-//! - Auto completion (not yet implemented)
+//! - Highlighting of known commands and usage hints while typing
+//! - Background jobs that can be listed, waited for and killed
+//! - A server that runs scopes for remote users over TCP or Unix domain sockets
+//! - Structured output that can be rendered for humans or as JSON lines
+//! - Styled output that is colored when writing to a terminal
+//! - Async commands, enabled with the `async` feature
+//! - Serializable scope descriptions, enabled with the `serde` feature
+//! - A JSON mode to drive scopes from other programs, enabled with the `json` feature
+//! - Tab completion of command names
+//! - Replaceable texts to translate the messages built into cmdr
+//! - Reference documentation as Markdown or man pages, generated from scopes
+//! - Shell completion scripts for bash, zsh and fish
+//! - Scopes with commands that are registered at runtime
+//! - Middleware that runs around every command, for logging, timing or authorization
+//! - A context with application state that is shared by all scopes
 //!
 //! To use CMDR you write the commands you want your user to interact with as functions on one or
 //! more Scope types. By implementing the scope trait cmdr can implement and execute your supplied
@@ -50,6 +64,14 @@
 //!     Ok(())
 //! }
 //! ```
+//! ## Upgrading from 0.3
+//! Version 0.4 changes some public types, code written for 0.3 needs these changes:
+//! - `Scope::run_command` gets an `&mut Interaction` instead of an `&mut dyn LineWriter`.
+//!   Interaction implements LineWriter, so implementations that only write output can pass it
+//!   on. Scopes generated by the cmdr macro are not affected.
+//! - `Action` has a new `Background` variant and `Error` has new variants for cancelled
+//!   commands, io errors and application errors. Matches on these enums need a wildcard arm.
+//!
 //! ## More information
 //! - [API documentation](https://docs.rs/cmdr/)
 //! - [Github repository](https://github.com/mendelt/cmdr)
@@ -68,26 +90,95 @@
     unused_qualifications
 )]
 
+#[cfg(feature = "async")]
+pub mod async_line_reader;
+#[cfg(feature = "async")]
+mod async_runner;
+#[cfg(feature = "async")]
+mod async_scope;
+mod cancel;
+pub mod client;
+pub mod completions;
+mod context;
 mod description;
+pub mod docs;
+mod dynamic_scope;
+mod format;
+mod future;
+mod global;
+mod helper;
+mod interaction;
+mod jobs;
+#[cfg(feature = "json")]
+mod json_runner;
 mod line;
 pub mod line_reader;
 pub mod line_writer;
+pub mod messages;
+mod middleware;
+mod pager;
+pub mod protocol;
+mod record;
 mod result;
+mod run_loop;
 mod runner;
 mod scope;
+pub mod server;
+mod style;
+mod terminal;
 
+#[cfg(feature = "async")]
+pub use crate::async_runner::AsyncRunner;
+#[cfg(feature = "async")]
+pub use crate::async_scope::{run_mounted_async, AsyncScope};
+pub use crate::cancel::CancelToken;
+pub use crate::context::Context;
 pub use crate::description::{ScopeCmdDescription, ScopeDescription};
+pub use crate::dynamic_scope::{CommandRegistry, DynamicScope};
+pub use crate::future::LocalBoxFuture;
+pub use crate::interaction::Interaction;
+pub use crate::jobs::Job;
+#[cfg(feature = "json")]
+pub use crate::json_runner::JsonRunner;
 pub use crate::line::Line;
 use crate::line_reader::RustyLineReader;
 pub use crate::line_writer::LineWriter;
-pub use crate::result::{Action, CommandResult, Error, Result};
+pub use crate::messages::Messages;
+pub use crate::middleware::{Middleware, Next};
+pub use crate::record::{Record, Value};
+pub use crate::result::{Action, CommandResult, Error, Result, ResultExt};
 pub use crate::runner::Runner;
-pub use crate::scope::Scope;
+pub use crate::scope::{run_mounted, Scope};
+pub use crate::style::Style;
 pub use cmdr_macro::{cmd, cmdr};
 use line_writer::PrintlnWriter;
 
+/// Async version of `cmd_loop`, awaits async commands on the running tokio runtime
+#[cfg(feature = "async")]
+pub async fn cmd_loop_async<S: Scope>(scope: &mut S) -> CommandResult {
+    let reader = async_line_reader::BlockingLineReader::new(RustyLineReader::new());
+    AsyncRunner::new(reader, PrintlnWriter {}).run(scope).await
+}
+
 /// This is the main entry-point to the cmdr library.
 /// Creates a LineReader and executes its command on the scope that is passed to it.
 pub fn cmd_loop<S: Scope>(scope: &mut S) -> CommandResult {
     Runner::new(RustyLineReader::new(), PrintlnWriter {}).run(scope)
 }
+
+/// Run the command loop for a scope from main() and exit the process when it finishes. Exits with
+/// 0 when the loop finishes normally, fatal errors exit with their error code and other errors are
+/// printed to stderr and exit with 1. Use `Runner::run_main` to get the exit code of a runner
+/// that is set up differently.
+pub fn cmd_main<S: Scope>(scope: &mut S) -> ! {
+    cmd_main_with(scope, Error::exit_code)
+}
+
+/// Like `cmd_main` but `exit_code` chooses the exit code for errors, for example to return
+/// different codes for application errors
+pub fn cmd_main_with<S: Scope, F: FnOnce(&Error) -> i32>(scope: &mut S, exit_code: F) -> ! {
+    let code =
+        Runner::new(RustyLineReader::new(), PrintlnWriter {}).run_main_with(scope, exit_code);
+
+    std::process::exit(code)
+}
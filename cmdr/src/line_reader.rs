@@ -1,29 +1,54 @@
 //! Contains the LineReader trait and several implementations to read lines from several sources
 
-use crate::Error;
-use rustyline::error::ReadlineError;
-use rustyline::Editor;
-use std::io::{BufRead, BufReader, Read};
+use crate::helper::CmdrHelper;
+use crate::terminal;
+use crate::{Error, ScopeDescription};
+use rustyline::{
+    Cmd, ConditionalEventHandler, Editor, Event, EventContext, EventHandler, KeyEvent, Movement,
+    RepeatCount,
+};
+use std::io::{stdout, BufRead, BufReader, ErrorKind, Read, Stdout, Write};
 
 /// Linereader trait, a line reader gets lines from a user, for example from the command line and
 /// parses them.
 pub trait LineReader {
     /// Blocks until a new line is entered
     fn read_line(&mut self, prompt: &str) -> Result<String, Error>;
+
+    /// Blocks until a password is entered, readers that read from a terminal should not show the
+    /// password while it is typed. The default implementation reads a normal line
+    fn read_password(&mut self, prompt: &str) -> Result<String, Error> {
+        self.read_line(prompt)
+    }
+
+    /// Called with the commands of the active scope before a line is read so the reader can use
+    /// them for highlighting and hints. The default implementation ignores them
+    fn set_commands(&mut self, _commands: &ScopeDescription) {}
 }
 
 /// Implementation of the LineReader trait using the rustyline library
 #[derive(Debug)]
 pub struct RustyLineReader {
-    editor: Editor<()>,
+    editor: Editor<CmdrHelper>,
 }
 
 impl RustyLineReader {
     /// Construct and return an new `RustyLineReader`
     pub fn new() -> Self {
-        RustyLineReader {
-            editor: Editor::<()>::new(),
-        }
+        let mut editor = Editor::<CmdrHelper>::new();
+        editor.set_helper(Some(CmdrHelper::default()));
+        editor.bind_sequence(
+            KeyEvent::ctrl('C'),
+            EventHandler::Conditional(Box::new(ClearLineOnCtrlC)),
+        );
+
+        RustyLineReader { editor }
+    }
+}
+
+impl Default for RustyLineReader {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
@@ -36,34 +61,125 @@ impl LineReader for RustyLineReader {
                 self.editor.add_history_entry(string_ref);
                 Ok(line_string)
             }
-            Err(ReadlineError::Interrupted) => Err(Error::CtrlC),
-            Err(ReadlineError::Eof) => Err(Error::CtrlD),
-            Err(_) => Err(Error::LineReaderError),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    fn read_password(&mut self, prompt: &str) -> Result<String, Error> {
+        if !terminal::is_interactive() {
+            return self.read_line(prompt);
+        }
+
+        print!("{} ", prompt);
+        stdout().flush().ok();
+        terminal::read_password().map_err(|error| match error.kind() {
+            ErrorKind::Interrupted => Error::CtrlC,
+            ErrorKind::UnexpectedEof => Error::CtrlD,
+            _ => Error::Io(error),
+        })
+    }
+
+    fn set_commands(&mut self, commands: &ScopeDescription) {
+        if let Some(helper) = self.editor.helper_mut() {
+            helper.set_commands(commands);
         }
     }
 }
 
-/// Wraps a LineReader and echoes all read lines
+/// Line reader for interactions when there is no user to answer questions, reading always fails
 #[derive(Debug)]
-pub struct EchoLineReader<W: LineReader> {
+pub(crate) struct NoLineReader;
+
+impl LineReader for NoLineReader {
+    fn read_line(&mut self, _: &str) -> Result<String, Error> {
+        Err(Error::LineReaderError)
+    }
+}
+
+/// Ctrl-C clears the line that is being typed, only Ctrl-C on an empty line interrupts
+#[derive(Debug)]
+struct ClearLineOnCtrlC;
+
+impl ConditionalEventHandler for ClearLineOnCtrlC {
+    fn handle(&self, _: &Event, _: RepeatCount, _: bool, context: &EventContext) -> Option<Cmd> {
+        if context.line().is_empty() {
+            None
+        } else {
+            Some(Cmd::Kill(Movement::WholeLine))
+        }
+    }
+}
+
+/// Wraps a LineReader and echoes all read lines, to stdout or to another output
+#[derive(Debug)]
+pub struct EchoLineReader<W: LineReader, O: Write = Stdout> {
     wrapped: W,
+    output: O,
 }
 
 impl<W: LineReader> EchoLineReader<W> {
-    /// Construct and return an new `EchoLineReader`
+    /// Construct and return an new `EchoLineReader` that echoes to stdout
     pub fn new(wrapped: W) -> Self {
-        EchoLineReader { wrapped }
+        EchoLineReader {
+            wrapped,
+            output: stdout(),
+        }
+    }
+}
+
+impl<W: LineReader, O: Write> EchoLineReader<W, O> {
+    /// Construct and return an new `EchoLineReader` that echoes to output
+    pub fn with_output(wrapped: W, output: O) -> Self {
+        EchoLineReader { wrapped, output }
     }
 }
 
-impl<W: LineReader> LineReader for EchoLineReader<W> {
+impl<W: LineReader, O: Write> LineReader for EchoLineReader<W, O> {
     fn read_line(&mut self, prompt: &str) -> Result<String, Error> {
-        match self.wrapped.read_line(prompt) {
-            Ok(line) => {
-                println!("{} {}", prompt, &line);
-                Ok(line)
-            }
-            Err(error) => Err(error),
+        let line = self.wrapped.read_line(prompt)?;
+        writeln!(self.output, "{} {}", prompt, &line)?;
+        Ok(line)
+    }
+
+    fn read_password(&mut self, prompt: &str) -> Result<String, Error> {
+        // Don't echo the password itself
+        let password = self.wrapped.read_password(prompt)?;
+        writeln!(self.output, "{}", prompt)?;
+        Ok(password)
+    }
+
+    fn set_commands(&mut self, commands: &ScopeDescription) {
+        self.wrapped.set_commands(commands)
+    }
+}
+
+/// Read commands from a stream like a socket, the prompt is written to a second stream that is
+/// usually the same socket
+#[derive(Debug)]
+pub struct StreamLineReader<R: Read, W: Write> {
+    reader: BufReader<R>,
+    prompt: W,
+}
+
+impl<R: Read, W: Write> StreamLineReader<R, W> {
+    /// Create a new StreamLineReader that reads from reader and writes prompts to prompt
+    pub fn new(reader: R, prompt: W) -> Self {
+        StreamLineReader {
+            reader: BufReader::new(reader),
+            prompt,
+        }
+    }
+}
+
+impl<R: Read, W: Write> LineReader for StreamLineReader<R, W> {
+    fn read_line(&mut self, prompt: &str) -> Result<String, Error> {
+        write!(self.prompt, "{} ", prompt).and_then(|_| self.prompt.flush())?;
+
+        let mut line = String::new();
+        match self.reader.read_line(&mut line) {
+            Ok(0) => Err(Error::CtrlD),
+            Ok(_) => Ok(line),
+            Err(error) => Err(error.into()),
         }
     }
 }
@@ -90,7 +206,36 @@ impl<R: Read> LineReader for FileLineReader<R> {
         match input {
             Ok(0) => Err(Error::CtrlD),
             Ok(_) => Ok(line),
-            Err(_) => Err(Error::LineReaderError),
+            Err(error) => Err(error.into()),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stream_line_reader_should_write_prompt() {
+        let mut prompt = Vec::new();
+        let mut reader = StreamLineReader::new(&b"greet\n"[..], &mut prompt);
+
+        assert_eq!(reader.read_line(">"), Ok("greet\n".to_string()));
+        assert_eq!(reader.read_line(">"), Err(Error::CtrlD));
+        assert_eq!(prompt, b"> > ");
+    }
+
+    #[test]
+    fn echo_line_reader_should_echo_to_output() {
+        let mut output = Vec::new();
+        let mut reader =
+            EchoLineReader::with_output(FileLineReader::new(&b"greet\nsecret\n"[..]), &mut output);
+
+        assert_eq!(reader.read_line(">"), Ok("greet\n".to_string()));
+        assert_eq!(
+            reader.read_password("Password:"),
+            Ok("secret\n".to_string())
+        );
+        assert_eq!(output, b"> greet\n\nPassword:\n");
+    }
+}
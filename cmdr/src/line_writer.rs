@@ -1,5 +1,12 @@
 //! Contains the LineWriter trait and some implementations to write lines to several destinations
+use crate::format::{format_list, format_paragraph, format_table, DEFAULT_WIDTH};
+use crate::messages::{DefaultMessages, Messages};
+use crate::pager::{Keys, Pager, TerminalKeys};
+use crate::record::{format_record, format_records, Record};
+use crate::style::Style;
+use crate::terminal;
 use std::io::{stdout, Write};
+use std::sync::Arc;
 
 /// A line writer handles output by writing lines of output to its destination
 pub trait LineWriter {
@@ -11,6 +18,138 @@ pub trait LineWriter {
 
     /// Write a string
     fn write(&mut self, line: &str);
+
+    /// Make sure everything that was written is shown. This is called after every command, the
+    /// default implementation does nothing
+    fn flush(&mut self) {}
+
+    /// The number of characters that fit on a line, used to wrap lists and paragraphs
+    fn width(&self) -> usize {
+        DEFAULT_WIDTH
+    }
+
+    /// Check if this writer renders styles, writers that don't write styled text as plain text
+    fn supports_style(&self) -> bool {
+        false
+    }
+
+    /// Check if this writer writes to the terminal the user types in, the `PagerWriter` only pages
+    /// output that goes to the terminal
+    fn is_terminal(&self) -> bool {
+        false
+    }
+
+    /// Use messages for the texts this writer shows itself, like the prompt of the `PagerWriter`.
+    /// Runners pass the messages they were given, the default implementation does nothing
+    fn set_messages(&mut self, _messages: Arc<dyn Messages>) {}
+
+    /// Write a string in the specified style
+    fn write_styled(&mut self, style: Style, text: &str) {
+        if self.supports_style() {
+            self.write(&style.paint(text));
+        } else {
+            self.write(text);
+        }
+    }
+
+    /// Write a line in the specified style, add a carriage return
+    fn write_styled_line(&mut self, style: Style, line: &str) {
+        self.write_styled(style, line);
+        self.write("\n");
+    }
+
+    /// Write a table with a header row, all columns are aligned and wide columns are wrapped to
+    /// fit the width
+    fn write_table(&mut self, headers: &[&str], rows: &[Vec<String>]) {
+        let table = format_table(headers, rows, self.width());
+        self.write_line(&table);
+    }
+
+    /// Write a list of names and values, values are aligned and wrapped to fit the width
+    fn write_list(&mut self, items: &[(&str, &str)]) {
+        let list = format_list(items, self.width());
+        self.write_line(&list);
+    }
+
+    /// Write a paragraph of text wrapped to fit the width
+    fn write_paragraph(&mut self, text: &str) {
+        let paragraph = format_paragraph(text, self.width());
+        self.write_line(&paragraph);
+    }
+
+    /// Write a structured record. The default implementation renders the record as a list of
+    /// names and values
+    fn write_record(&mut self, record: &Record) {
+        let list = format_record(record, self.width());
+        self.write_line(&list);
+    }
+
+    /// Write a list of structured records. The default implementation renders the records as a
+    /// table
+    fn write_records(&mut self, records: &[Record]) {
+        let table = format_records(records, self.width());
+        self.write_line(&table);
+    }
+}
+
+/// A mutable reference to a LineWriter is a LineWriter as well, so writers can wrap borrowed
+/// writers
+impl<W: LineWriter + ?Sized> LineWriter for &mut W {
+    fn write_line(&mut self, line: &str) {
+        (**self).write_line(line)
+    }
+
+    fn write(&mut self, line: &str) {
+        (**self).write(line)
+    }
+
+    fn flush(&mut self) {
+        (**self).flush()
+    }
+
+    fn width(&self) -> usize {
+        (**self).width()
+    }
+
+    fn supports_style(&self) -> bool {
+        (**self).supports_style()
+    }
+
+    fn is_terminal(&self) -> bool {
+        (**self).is_terminal()
+    }
+
+    fn set_messages(&mut self, messages: Arc<dyn Messages>) {
+        (**self).set_messages(messages)
+    }
+
+    fn write_styled(&mut self, style: Style, text: &str) {
+        (**self).write_styled(style, text)
+    }
+
+    fn write_styled_line(&mut self, style: Style, line: &str) {
+        (**self).write_styled_line(style, line)
+    }
+
+    fn write_table(&mut self, headers: &[&str], rows: &[Vec<String>]) {
+        (**self).write_table(headers, rows)
+    }
+
+    fn write_list(&mut self, items: &[(&str, &str)]) {
+        (**self).write_list(items)
+    }
+
+    fn write_paragraph(&mut self, text: &str) {
+        (**self).write_paragraph(text)
+    }
+
+    fn write_record(&mut self, record: &Record) {
+        (**self).write_record(record)
+    }
+
+    fn write_records(&mut self, records: &[Record]) {
+        (**self).write_records(records)
+    }
 }
 
 /// Write lines to stdout
@@ -19,6 +158,287 @@ pub struct PrintlnWriter {}
 
 impl LineWriter for PrintlnWriter {
     fn write(&mut self, line: &str) {
-        stdout().write(line.as_bytes()).ok();
+        stdout().write_all(line.as_bytes()).ok();
+    }
+
+    fn flush(&mut self) {
+        stdout().flush().ok();
+    }
+
+    fn width(&self) -> usize {
+        terminal::width().unwrap_or(DEFAULT_WIDTH)
+    }
+
+    fn supports_style(&self) -> bool {
+        terminal::use_color()
+    }
+
+    fn is_terminal(&self) -> bool {
+        terminal::is_interactive()
+    }
+}
+
+/// Write lines to an io stream like a file or socket
+#[derive(Debug)]
+pub struct StreamLineWriter<W: Write> {
+    stream: W,
+}
+
+impl<W: Write> StreamLineWriter<W> {
+    /// Construct and return a new `StreamLineWriter`
+    pub fn new(stream: W) -> Self {
+        StreamLineWriter { stream }
+    }
+}
+
+impl<W: Write> LineWriter for StreamLineWriter<W> {
+    fn write(&mut self, line: &str) {
+        self.stream.write_all(line.as_bytes()).ok();
+    }
+
+    fn flush(&mut self) {
+        self.stream.flush().ok();
+    }
+}
+
+/// Wraps a LineWriter and writes structured records as JSON lines, one JSON object per line, so
+/// output can be consumed by scripts
+#[derive(Debug)]
+pub struct JsonWriter<W: LineWriter> {
+    wrapped: W,
+}
+
+impl<W: LineWriter> JsonWriter<W> {
+    /// Construct and return a new `JsonWriter`
+    pub fn new(wrapped: W) -> Self {
+        JsonWriter { wrapped }
+    }
+}
+
+impl<W: LineWriter> LineWriter for JsonWriter<W> {
+    fn write(&mut self, line: &str) {
+        self.wrapped.write(line)
+    }
+
+    fn flush(&mut self) {
+        self.wrapped.flush()
+    }
+
+    fn width(&self) -> usize {
+        self.wrapped.width()
+    }
+
+    fn supports_style(&self) -> bool {
+        self.wrapped.supports_style()
+    }
+
+    fn is_terminal(&self) -> bool {
+        self.wrapped.is_terminal()
+    }
+
+    fn set_messages(&mut self, messages: Arc<dyn Messages>) {
+        self.wrapped.set_messages(messages)
+    }
+
+    fn write_record(&mut self, record: &Record) {
+        self.wrapped.write_line(&record.to_json())
+    }
+
+    fn write_records(&mut self, records: &[Record]) {
+        for record in records {
+            self.write_record(record)
+        }
+    }
+}
+
+/// Wraps a LineWriter and pages output that does not fit on the terminal. When the screen is
+/// full the user is asked to press space for the next page, enter for the next line, / to search
+/// or q to skip the rest of the output of the command. Output is passed on directly when the
+/// wrapped writer does not write to the terminal.
+///
+/// When combined with a `JsonWriter` the `PagerWriter` should be the inner writer.
+#[derive(Debug)]
+pub struct PagerWriter<W: LineWriter> {
+    wrapped: W,
+    line: String,
+    pager: Option<Pager>,
+    keys: Box<dyn Keys>,
+    messages: Arc<dyn Messages>,
+}
+
+impl<W: LineWriter> PagerWriter<W> {
+    /// Construct and return a new `PagerWriter`
+    pub fn new(wrapped: W) -> Self {
+        PagerWriter {
+            wrapped,
+            line: String::new(),
+            pager: None,
+            keys: Box::new(TerminalKeys),
+            messages: Arc::new(DefaultMessages),
+        }
+    }
+
+    /// Show messages instead of the default English texts in the pager prompts. A runner that has
+    /// messages of its own passes them to the pager instead
+    pub fn with_messages<M: Messages + 'static>(mut self, messages: M) -> Self {
+        self.messages = Arc::new(messages);
+        self
+    }
+
+    /// Pass text to the pager, the page size is looked up when a command starts writing
+    fn show(&mut self, text: &str, newline: bool) {
+        let pager = self
+            .pager
+            .get_or_insert_with(|| Pager::new(terminal::height().unwrap_or(0).saturating_sub(1)));
+        pager.show(
+            text,
+            newline,
+            &mut self.wrapped,
+            self.keys.as_mut(),
+            &*self.messages,
+        );
+    }
+}
+
+impl<W: LineWriter> LineWriter for PagerWriter<W> {
+    fn write(&mut self, line: &str) {
+        if !self.wrapped.is_terminal() {
+            return self.wrapped.write(line);
+        }
+
+        // Complete lines are paged right away, the rest is kept until the line is complete
+        let mut parts = line.split('\n');
+        if let Some(first) = parts.next() {
+            self.line.push_str(first);
+        }
+        for part in parts {
+            let complete = std::mem::replace(&mut self.line, part.to_string());
+            self.show(&complete, true);
+        }
+    }
+
+    fn flush(&mut self) {
+        if !self.line.is_empty() {
+            let partial = std::mem::take(&mut self.line);
+            self.show(&partial, false);
+        }
+        if let Some(mut pager) = self.pager.take() {
+            pager.finish(&mut self.wrapped, &*self.messages);
+        }
+        self.wrapped.flush();
+    }
+
+    fn width(&self) -> usize {
+        self.wrapped.width()
+    }
+
+    fn supports_style(&self) -> bool {
+        self.wrapped.supports_style()
+    }
+
+    fn is_terminal(&self) -> bool {
+        self.wrapped.is_terminal()
+    }
+
+    fn set_messages(&mut self, messages: Arc<dyn Messages>) {
+        self.messages = messages.clone();
+        self.wrapped.set_messages(messages)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    impl LineWriter for String {
+        fn write(&mut self, line: &str) {
+            self.push_str(line)
+        }
+    }
+
+    #[test]
+    fn json_writer_should_write_a_json_line_per_record() {
+        let mut writer = JsonWriter::new(String::new());
+
+        writer.write_records(&[
+            Record::new().field("name", "Bob"),
+            Record::new().field("name", "Alice"),
+        ]);
+
+        assert_eq!(writer.wrapped, "{\"name\":\"Bob\"}\n{\"name\":\"Alice\"}\n");
+    }
+
+    #[test]
+    fn writer_without_style_support_should_write_plain_text() {
+        let mut writer = String::new();
+
+        writer.write_styled_line(Style::Error, "error");
+
+        assert_eq!(writer, "error\n");
+    }
+
+    #[test]
+    fn pager_writer_should_pass_on_output_when_not_on_terminal() {
+        let mut writer = PagerWriter::new(String::new());
+
+        writer.write_line("first");
+        writer.write("second");
+
+        assert_eq!(writer.wrapped, "first\nsecond");
+    }
+
+    /// A terminal that can not tell its height, so output is never paged
+    #[derive(Debug, Default)]
+    struct Terminal(String);
+
+    impl LineWriter for Terminal {
+        fn write(&mut self, line: &str) {
+            self.0.push_str(line)
+        }
+
+        fn is_terminal(&self) -> bool {
+            true
+        }
+    }
+
+    #[test]
+    fn pager_writer_should_show_complete_lines_and_partial_lines_when_flushed() {
+        let mut writer = PagerWriter::new(Terminal::default());
+
+        writer.write("first\nsec");
+        writer.write("ond\nthird");
+        assert_eq!(writer.wrapped.0, "first\nsecond\n");
+
+        writer.flush();
+        assert_eq!(writer.wrapped.0, "first\nsecond\nthird");
+    }
+
+    /// Messages with another pager prompt
+    #[derive(Debug)]
+    struct DutchMessages;
+
+    impl Messages for DutchMessages {
+        fn pager_more(&self) -> String {
+            "--Meer--".to_string()
+        }
+    }
+
+    #[test]
+    fn pager_writer_should_use_messages_passed_through_wrappers() {
+        let mut writer = JsonWriter::new(PagerWriter::new(String::new()));
+
+        let dynamic: &mut dyn LineWriter = &mut writer;
+        dynamic.set_messages(Arc::new(DutchMessages));
+
+        assert_eq!(writer.wrapped.messages.pager_more(), "--Meer--");
+    }
+
+    #[test]
+    fn json_writer_should_pass_on_plain_lines() {
+        let mut writer = JsonWriter::new(String::new());
+
+        writer.write_line("plain text");
+
+        assert_eq!(writer.wrapped, "plain text\n");
     }
 }
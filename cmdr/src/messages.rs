@@ -0,0 +1,161 @@
+//! Texts built into cmdr that are shown to the user. Implement Messages and pass it to a runner
+//! to translate or change them.
+use crate::result::Error;
+use std::fmt::Debug;
+
+/// All texts cmdr shows to the user. Every method returns the English text by default, implement
+/// the methods for the texts you want to change and pass the implementation to
+/// `Runner::with_messages`.
+pub trait Messages: Debug + Send + Sync {
+    /// Message shown for errors handled by cmdr, like unknown commands
+    fn error(&self, error: &Error) -> String {
+        error.to_string()
+    }
+
+    /// First line of the report for an error returned by the application
+    fn application_error(&self, message: &str) -> String {
+        format!("Error: {}", message)
+    }
+
+    /// Line in an error report for a cause of the error
+    fn caused_by(&self, message: &str) -> String {
+        format!("Caused by: {}", message)
+    }
+
+    /// Header of the command list shown by help for scopes without help text
+    fn help_header(&self) -> String {
+        "These are the valid commands in this scope:".to_string()
+    }
+
+    /// Header of the list of global commands shown by help
+    fn global_help_header(&self) -> String {
+        "These commands are available in every scope:".to_string()
+    }
+
+    /// Header of the list of commands to manage background jobs shown by help
+    fn jobs_help_header(&self) -> String {
+        "These commands manage background jobs:".to_string()
+    }
+
+    /// Header of the list of commands of a mounted scope shown by help
+    fn mount_help_header(&self, name: &str) -> String {
+        format!("Commands of {}:", name)
+    }
+
+    /// Usage line shown by help for a command
+    fn usage(&self, command: &str, usage: &str) -> String {
+        format!("Usage: {} {}", command, usage)
+    }
+
+    /// Prompt for a yes/no question
+    fn confirm_prompt(&self, question: &str) -> String {
+        format!("{} [y/N]", question)
+    }
+
+    /// Interpret the answer to a yes/no question, returns None when the answer is not valid
+    fn confirm_answer(&self, answer: &str) -> Option<bool> {
+        match answer.trim().to_lowercase().as_ref() {
+            "y" | "yes" => Some(true),
+            "" | "n" | "no" => Some(false),
+            _ => None,
+        }
+    }
+
+    /// Shown when the answer to a yes/no question is not valid
+    fn confirm_invalid(&self) -> String {
+        "Please answer y or n".to_string()
+    }
+
+    /// Prompt for choosing from a numbered list of options
+    fn select_prompt(&self, prompt: &str, options: usize) -> String {
+        format!("{} [1-{}]", prompt, options)
+    }
+
+    /// Error when there are no options to choose from
+    fn select_empty(&self) -> String {
+        "Nothing to select from".to_string()
+    }
+
+    /// Shown when the chosen option is not valid
+    fn select_invalid(&self, options: usize) -> String {
+        format!("Please enter a number from 1 to {}", options)
+    }
+
+    /// Shown by the pager when there is more output
+    fn pager_more(&self) -> String {
+        "--More--".to_string()
+    }
+
+    /// Shown by the pager when it skips lines to show a search result
+    fn pager_skipping(&self) -> String {
+        "...skipping".to_string()
+    }
+
+    /// Shown by the pager when a search has no results
+    fn pager_not_found(&self) -> String {
+        "Pattern not found".to_string()
+    }
+
+    /// Sent by a server to a connection it refuses because it serves too many connections
+    fn too_many_connections(&self) -> String {
+        "Too many connections, try again later".to_string()
+    }
+
+    /// Help text of the jobs command
+    fn jobs_help(&self) -> String {
+        "List the background jobs that are running".to_string()
+    }
+
+    /// Help text of the wait command
+    fn wait_help(&self) -> String {
+        "Wait for a background job, or all jobs, to finish".to_string()
+    }
+
+    /// Help text of the kill command
+    fn kill_help(&self) -> String {
+        "Ask a background job to stop".to_string()
+    }
+
+    /// Shown by the jobs command when no jobs are running
+    fn no_jobs(&self) -> String {
+        "No jobs running".to_string()
+    }
+
+    /// Shown when a job that is waited for or killed does not exist
+    fn no_such_job(&self, job: &str) -> String {
+        format!("No such job: {}", job)
+    }
+
+    /// Notice for a job that finished
+    fn job_done(&self, id: usize, name: &str) -> String {
+        format!("[{}] Done    {}", id, name)
+    }
+
+    /// Notice for a job that stopped after it was killed
+    fn job_killed(&self, id: usize, name: &str) -> String {
+        format!("[{}] Killed  {}", id, name)
+    }
+
+    /// Notice for a job that failed with an error or panicked
+    fn job_failed(&self, id: usize, name: &str, error: &str) -> String {
+        format!("[{}] Failed  {}: {}", id, name, error)
+    }
+}
+
+/// The English texts cmdr shows by default
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DefaultMessages;
+
+impl Messages for DefaultMessages {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_interpret_confirm_answers() {
+        assert_eq!(DefaultMessages.confirm_answer("Yes"), Some(true));
+        assert_eq!(DefaultMessages.confirm_answer(""), Some(false));
+        assert_eq!(DefaultMessages.confirm_answer("maybe"), None);
+    }
+}
@@ -0,0 +1,206 @@
+//! Middleware runs around every command the runner executes
+use crate::description::ScopeCmdDescription;
+use crate::future::LocalBoxFuture;
+use crate::line::Line;
+use crate::line_writer::LineWriter;
+use crate::result::CommandResult;
+use std::fmt::{Debug, Formatter};
+
+/// Middleware wraps the commands a runner executes, for things like timing, audit logging or
+/// authorization checks that are needed in every scope. Middleware is added to a runner with
+/// `Runner::with_middleware` and runs in the order it was added, the first middleware is the
+/// outermost. Middleware returns a future so it can wrap async commands of the `AsyncRunner` as
+/// well:
+///
+/// ```
+/// use cmdr::*;
+///
+/// #[derive(Debug)]
+/// struct Trace;
+///
+/// impl Middleware for Trace {
+///     fn handle<'a>(
+///         &'a mut self,
+///         line: &'a Line,
+///         _command: &'a ScopeCmdDescription,
+///         mut next: Next<'a>,
+///     ) -> LocalBoxFuture<'a, CommandResult> {
+///         Box::pin(async move {
+///             let result = next.run().await;
+///             next.writer().write_line(&format!("{} done", line.command));
+///             result
+///         })
+///     }
+/// }
+/// ```
+pub trait Middleware: Debug {
+    /// Called for a command that is about to run. Await `next.run()` to run the rest of the chain
+    /// and the command itself, or return without running it to stop the command
+    fn handle<'a>(
+        &'a mut self,
+        line: &'a Line,
+        command: &'a ScopeCmdDescription,
+        next: Next<'a>,
+    ) -> LocalBoxFuture<'a, CommandResult>;
+}
+
+/// Runs the command at the end of the middleware chain
+pub(crate) trait RunCommand {
+    fn run<'a>(&'a mut self, writer: &'a mut dyn LineWriter) -> LocalBoxFuture<'a, CommandResult>;
+}
+
+/// The rest of the middleware chain and the command it wraps
+pub struct Next<'a> {
+    line: &'a Line,
+    command: &'a ScopeCmdDescription,
+    middleware: &'a mut [Box<dyn Middleware>],
+    writer: &'a mut dyn LineWriter,
+    run: &'a mut dyn RunCommand,
+}
+
+impl<'a> Next<'a> {
+    pub(crate) fn new(
+        line: &'a Line,
+        command: &'a ScopeCmdDescription,
+        middleware: &'a mut [Box<dyn Middleware>],
+        writer: &'a mut dyn LineWriter,
+        run: &'a mut dyn RunCommand,
+    ) -> Self {
+        Next {
+            line,
+            command,
+            middleware,
+            writer,
+            run,
+        }
+    }
+
+    /// Writer for output of the middleware
+    pub fn writer(&mut self) -> &mut dyn LineWriter {
+        self.writer
+    }
+
+    /// Run the next middleware, or the command when this is the last middleware
+    pub fn run(&mut self) -> LocalBoxFuture<'_, CommandResult> {
+        match self.middleware.split_first_mut() {
+            Some((first, rest)) => first.handle(
+                self.line,
+                self.command,
+                Next::new(self.line, self.command, rest, self.writer, self.run),
+            ),
+            None => self.run.run(self.writer),
+        }
+    }
+}
+
+impl Debug for Next<'_> {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> std::fmt::Result {
+        formatter
+            .debug_struct("Next")
+            .field("line", &self.line)
+            .field("command", &self.command)
+            .field("middleware", &self.middleware)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::future::block_on;
+    use crate::result::{Action, Error};
+
+    /// Writes its name before and after the rest of the chain
+    #[derive(Debug)]
+    struct Trace(&'static str);
+
+    impl Middleware for Trace {
+        fn handle<'a>(
+            &'a mut self,
+            _: &'a Line,
+            _: &'a ScopeCmdDescription,
+            mut next: Next<'a>,
+        ) -> LocalBoxFuture<'a, CommandResult> {
+            Box::pin(async move {
+                next.writer().write_line(&format!("before {}", self.0));
+                let result = next.run().await;
+                next.writer().write_line(&format!("after {}", self.0));
+                result
+            })
+        }
+    }
+
+    /// Only allows commands without arguments
+    #[derive(Debug)]
+    struct NoArgs;
+
+    impl Middleware for NoArgs {
+        fn handle<'a>(
+            &'a mut self,
+            line: &'a Line,
+            _: &'a ScopeCmdDescription,
+            mut next: Next<'a>,
+        ) -> LocalBoxFuture<'a, CommandResult> {
+            Box::pin(async move {
+                if line.args.is_empty() {
+                    next.run().await
+                } else {
+                    Err(Error::InvalidNumberOfArguments(line.command.clone()))
+                }
+            })
+        }
+    }
+
+    /// A command that writes a line
+    struct WriteCommand;
+
+    impl RunCommand for WriteCommand {
+        fn run<'a>(
+            &'a mut self,
+            writer: &'a mut dyn LineWriter,
+        ) -> LocalBoxFuture<'a, CommandResult> {
+            Box::pin(async move {
+                writer.write_line("command");
+                Ok(Action::Done)
+            })
+        }
+    }
+
+    fn run_chain(middleware: &mut [Box<dyn Middleware>], line: &str) -> (CommandResult, String) {
+        let line = Line::try_parse(line).unwrap();
+        let command = ScopeCmdDescription::new(line.command.clone(), vec![], None);
+        let mut output = String::new();
+
+        let result =
+            block_on(Next::new(&line, &command, middleware, &mut output, &mut WriteCommand).run());
+        (result, output)
+    }
+
+    #[test]
+    fn should_run_middleware_in_order() {
+        let (result, output) = run_chain(
+            &mut [Box::new(Trace("first")), Box::new(Trace("second"))],
+            "greet",
+        );
+
+        assert!(matches!(result, Ok(Action::Done)));
+        assert_eq!(
+            output,
+            "before first\nbefore second\ncommand\nafter second\nafter first\n"
+        );
+    }
+
+    #[test]
+    fn should_stop_when_middleware_does_not_call_next() {
+        let (result, output) = run_chain(
+            &mut [Box::new(NoArgs), Box::new(Trace("last"))],
+            "greet Bob",
+        );
+
+        assert_eq!(
+            result.unwrap_err(),
+            Error::InvalidNumberOfArguments("greet".to_string())
+        );
+        assert_eq!(output, "");
+    }
+}
@@ -0,0 +1,297 @@
+//! A more-style pager that shows long output one page at a time
+use crate::line_writer::LineWriter;
+use crate::messages::Messages;
+use crate::style::Style;
+use crate::terminal;
+use std::fmt::Debug;
+
+/// Reads the keys that control the pager
+pub(crate) trait Keys: Debug {
+    /// Read a single key press, None when no key can be read
+    fn read_key(&mut self) -> Option<char>;
+
+    /// Read the pattern to search for
+    fn read_line(&mut self) -> Option<String>;
+}
+
+/// Reads keys from the terminal
+#[derive(Debug)]
+pub(crate) struct TerminalKeys;
+
+impl Keys for TerminalKeys {
+    fn read_key(&mut self) -> Option<char> {
+        terminal::read_key()
+    }
+
+    fn read_line(&mut self) -> Option<String> {
+        terminal::read_line()
+    }
+}
+
+/// What the pager does with the next line
+#[derive(Debug, PartialEq)]
+enum State {
+    Show,
+    Search(String),
+    Quit,
+}
+
+/// Shows output line by line and asks the user to continue each time the screen is full
+#[derive(Debug)]
+pub(crate) struct Pager {
+    page_size: usize,
+    remaining: usize,
+    state: State,
+    last_search: Option<String>,
+}
+
+impl Pager {
+    /// Create a pager that shows page_size rows at a time, a page size of 0 never asks
+    pub(crate) fn new(page_size: usize) -> Self {
+        Pager {
+            page_size,
+            remaining: page_size,
+            state: State::Show,
+            last_search: None,
+        }
+    }
+
+    /// Show text, followed by a line break when newline is set. When the wrapped text does not fit
+    /// on the rest of the screen the user is asked to press space for the next page, enter for
+    /// the next line, / to search or q to quit
+    pub(crate) fn show(
+        &mut self,
+        text: &str,
+        newline: bool,
+        writer: &mut dyn LineWriter,
+        keys: &mut dyn Keys,
+        messages: &dyn Messages,
+    ) {
+        let rows = rows(text, writer.width());
+
+        loop {
+            match &self.state {
+                State::Quit => return,
+                State::Search(pattern) => {
+                    if !text.contains(pattern.as_str()) {
+                        return;
+                    }
+                    writer.write_styled_line(Style::Dim, &messages.pager_skipping());
+                    self.state = State::Show;
+                    self.remaining = self.page_size.saturating_sub(1);
+                }
+                // A line that is longer than a page is shown at the top of a new page
+                State::Show if rows <= self.remaining || self.remaining == self.page_size => break,
+                State::Show => self.ask(rows, writer, keys, messages),
+            }
+        }
+
+        writer.write(text);
+        if newline {
+            writer.write("\n");
+        }
+        self.remaining = self.remaining.saturating_sub(rows);
+    }
+
+    /// End the output of a command, a search that did not find anything is reported
+    pub(crate) fn finish(&mut self, writer: &mut dyn LineWriter, messages: &dyn Messages) {
+        if let State::Search(_) = self.state {
+            writer.write_styled_line(Style::Warning, &messages.pager_not_found());
+        }
+        self.state = State::Show;
+        self.remaining = self.page_size;
+    }
+
+    /// Ask the user what to do before showing a line of rows that does not fit
+    fn ask(
+        &mut self,
+        rows: usize,
+        writer: &mut dyn LineWriter,
+        keys: &mut dyn Keys,
+        messages: &dyn Messages,
+    ) {
+        writer.write_styled(Style::Dim, &messages.pager_more());
+        writer.flush();
+
+        let key = keys.read_key();
+        writer.write("\r\x1b[K");
+
+        match key {
+            Some(' ') => self.remaining = self.page_size,
+            Some('\n') | Some('\r') => self.remaining = rows,
+            Some('/') => {
+                writer.write("/");
+                writer.flush();
+                let pattern = keys.read_line().unwrap_or_default();
+                self.search(pattern, writer, messages);
+            }
+            Some('n') => self.search(String::new(), writer, messages),
+            // Ctrl-C is read as a key while the pager waits
+            Some('q') | Some('Q') | Some('\x03') | None => self.state = State::Quit,
+            Some(_) => (),
+        }
+    }
+
+    /// Skip lines until one contains pattern, repeats the last search when pattern is empty
+    fn search(&mut self, pattern: String, writer: &mut dyn LineWriter, messages: &dyn Messages) {
+        if !pattern.is_empty() {
+            self.last_search = Some(pattern);
+        }
+
+        match &self.last_search {
+            Some(pattern) => self.state = State::Search(pattern.clone()),
+            None => writer.write_styled_line(Style::Warning, &messages.pager_not_found()),
+        }
+    }
+}
+
+/// Number of rows text takes on a terminal that wraps at width, escape codes take no room
+fn rows(text: &str, width: usize) -> usize {
+    let width = width.max(1);
+    let mut columns = 0;
+    let mut chars = text.chars();
+
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            // Skip the rest of the escape code, it ends with a letter
+            for c in &mut chars {
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        } else if !c.is_control() {
+            columns += 1;
+        }
+    }
+
+    ((columns + width - 1) / width).max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::messages::DefaultMessages;
+    use std::collections::VecDeque;
+
+    #[derive(Debug)]
+    struct ScriptedKeys(VecDeque<&'static str>);
+
+    impl Keys for ScriptedKeys {
+        fn read_key(&mut self) -> Option<char> {
+            self.0.pop_front()?.chars().next()
+        }
+
+        fn read_line(&mut self) -> Option<String> {
+            self.0.pop_front().map(str::to_string)
+        }
+    }
+
+    /// A terminal that is 10 characters wide
+    #[derive(Debug, Default)]
+    struct Screen(String);
+
+    impl LineWriter for Screen {
+        fn write(&mut self, line: &str) {
+            // Leave out the prompt and the code that clears it
+            if line != "--More--" && line != "\r\x1b[K" {
+                self.0.push_str(line)
+            }
+        }
+
+        fn width(&self) -> usize {
+            10
+        }
+    }
+
+    /// Show lines with a page size of 4 while pressing keys, returns the output
+    fn page(lines: &[&str], keys: &[&'static str]) -> String {
+        let mut pager = Pager::new(4);
+        let mut keys = ScriptedKeys(keys.iter().cloned().collect());
+        let mut screen = Screen::default();
+
+        for line in lines {
+            pager.show(line, true, &mut screen, &mut keys, &DefaultMessages);
+        }
+        pager.finish(&mut screen, &DefaultMessages);
+        screen.0
+    }
+
+    fn numbered(count: usize) -> Vec<String> {
+        (1..=count).map(|line| format!("line {}", line)).collect()
+    }
+
+    fn page_numbered(count: usize, keys: &[&'static str]) -> String {
+        let lines = numbered(count);
+        page(&lines.iter().map(String::as_str).collect::<Vec<_>>(), keys)
+    }
+
+    #[test]
+    fn pager_should_show_a_page_at_a_time() {
+        assert_eq!(page_numbered(6, &["q"]), "line 1\nline 2\nline 3\nline 4\n");
+        assert_eq!(
+            page_numbered(10, &[" ", " "]),
+            numbered(10).join("\n") + "\n"
+        );
+    }
+
+    #[test]
+    fn pager_should_advance_single_lines() {
+        assert_eq!(
+            page_numbered(10, &["\n", "q"]),
+            "line 1\nline 2\nline 3\nline 4\nline 5\n"
+        );
+    }
+
+    #[test]
+    fn pager_should_count_wrapped_rows() {
+        assert_eq!(
+            page(
+                &["a long line of text", "another long line", "next"],
+                &["q"]
+            ),
+            "a long line of text\nanother long line\n"
+        );
+    }
+
+    #[test]
+    fn pager_should_skip_to_search_result() {
+        assert_eq!(
+            page_numbered(10, &["/", "line 7", "q"]),
+            "line 1\nline 2\nline 3\nline 4\n/...skipping\nline 7\nline 8\nline 9\n"
+        );
+    }
+
+    #[test]
+    fn pager_should_report_search_without_result() {
+        assert_eq!(
+            page_numbered(6, &["/", "nothing"]),
+            "line 1\nline 2\nline 3\nline 4\n/Pattern not found\n"
+        );
+    }
+
+    #[test]
+    fn pager_should_repeat_last_search() {
+        assert_eq!(
+            page(
+                &["match", "a", "b", "c", "d", "match", "e", "f", "g", "match"],
+                &["/", "match", "n"]
+            ),
+            "match\na\nb\nc\n/...skipping\nmatch\ne\nf\n...skipping\nmatch\n"
+        );
+    }
+
+    #[test]
+    fn pager_should_stop_on_ctrl_c() {
+        assert_eq!(
+            page_numbered(6, &["\x03"]),
+            "line 1\nline 2\nline 3\nline 4\n"
+        );
+    }
+
+    #[test]
+    fn rows_should_ignore_escape_codes() {
+        assert_eq!(rows(&Style::Error.paint("0123456789"), 10), 1);
+        assert_eq!(rows("01234567890", 10), 2);
+        assert_eq!(rows("", 10), 1);
+    }
+}
@@ -0,0 +1,622 @@
+//! The framed protocol that is used between a cmdr server and `cmdr-client`. Every frame starts
+//! with a header line containing the frame type and the length of the payload in bytes, followed
+//! by the payload itself:
+//!
+//! ```text
+//! output 6
+//! Hello
+//! prompt 1
+//! >
+//! ```
+//!
+//! Payloads are at most `MAX_FRAME_SIZE` bytes, longer output is sent in several frames.
+//!
+//! Frames let the client tell prompts apart from output and know when the session has ended.
+//! While the server waits for a line the client can ask for the commands of the current scope or
+//! for completions, so remote sessions get the same hints and completion as local ones.
+
+use crate::description::{ScopeCmdDescription, ScopeDescription};
+use crate::helper::complete_command;
+use crate::line_reader::LineReader;
+use crate::line_writer::LineWriter;
+use crate::result::Error;
+use std::io::{self, BufRead, BufReader, ErrorKind, Read, Write};
+
+/// The largest payload a frame can have, longer frames are refused so a peer can not make the
+/// other side allocate any amount of memory
+pub const MAX_FRAME_SIZE: usize = 1024 * 1024;
+
+/// The longest header line, a frame type and a length fit easily
+const MAX_HEADER_SIZE: u64 = 64;
+
+/// A single message of the protocol
+#[derive(Debug, Clone, PartialEq)]
+pub enum Frame {
+    /// A line entered by the user, sent by the client
+    Line(String),
+
+    /// The server waits for a line and shows this prompt
+    Prompt(String),
+
+    /// The server waits for a password and shows this prompt, the password should not be shown
+    Password(String),
+
+    /// Output of a command
+    Output(String),
+
+    /// The session is over, can be sent by both sides
+    End,
+
+    /// Ask for the commands of the current scope, sent by the client while the server waits for a
+    /// line
+    Describe,
+
+    /// The commands of the current scope, the answer to Describe
+    Description(ScopeDescription),
+
+    /// Ask for completions of the command name at the end of a partial line, sent by the client
+    /// while the server waits for a line
+    Complete(String),
+
+    /// Command names the partial line can be completed to, the answer to Complete
+    Completions(Vec<String>),
+}
+
+impl Frame {
+    fn kind(&self) -> &'static str {
+        match self {
+            Frame::Line(_) => "line",
+            Frame::Prompt(_) => "prompt",
+            Frame::Password(_) => "password",
+            Frame::Output(_) => "output",
+            Frame::End => "end",
+            Frame::Describe => "describe",
+            Frame::Description(_) => "description",
+            Frame::Complete(_) => "complete",
+            Frame::Completions(_) => "completions",
+        }
+    }
+
+    fn payload(&self) -> String {
+        match self {
+            Frame::Line(text)
+            | Frame::Prompt(text)
+            | Frame::Password(text)
+            | Frame::Output(text)
+            | Frame::Complete(text) => text.clone(),
+            Frame::End | Frame::Describe => String::new(),
+            Frame::Description(description) => encode_description(description),
+            Frame::Completions(completions) => completions.join("\n"),
+        }
+    }
+
+    /// Write the frame to a stream and flush it, fails with `InvalidInput` when the payload is
+    /// longer than `MAX_FRAME_SIZE`
+    pub fn write_to<W: Write + ?Sized>(&self, stream: &mut W) -> io::Result<()> {
+        let payload = self.payload();
+        if payload.len() > MAX_FRAME_SIZE {
+            return Err(io::Error::new(ErrorKind::InvalidInput, "Frame too long"));
+        }
+
+        write!(stream, "{} {}\n{}", self.kind(), payload.len(), payload)?;
+        stream.flush()
+    }
+
+    /// Read the next frame from a stream, returns None when the stream is closed. Fails with
+    /// `InvalidData` when the frame is invalid or longer than `MAX_FRAME_SIZE`
+    pub fn read_from<R: BufRead + ?Sized>(stream: &mut R) -> io::Result<Option<Frame>> {
+        let mut header = String::new();
+        match (&mut *stream)
+            .take(MAX_HEADER_SIZE)
+            .read_line(&mut header)?
+        {
+            0 => return Ok(None),
+            read if read as u64 == MAX_HEADER_SIZE && !header.ends_with('\n') => {
+                return Err(invalid_data("Frame header too long"))
+            }
+            _ => (),
+        }
+
+        let mut parts = header.trim_end().splitn(2, ' ');
+        let kind = parts.next().unwrap_or_default().to_string();
+        let length: usize = parts
+            .next()
+            .and_then(|length| length.parse().ok())
+            .ok_or_else(|| invalid_data("Invalid frame header"))?;
+        if length > MAX_FRAME_SIZE {
+            return Err(invalid_data("Frame too long"));
+        }
+
+        let mut payload = vec![0; length];
+        stream.read_exact(&mut payload)?;
+        let payload = String::from_utf8(payload).map_err(|_| invalid_data("Invalid utf-8"))?;
+
+        match kind.as_ref() {
+            "line" => Ok(Some(Frame::Line(payload))),
+            "prompt" => Ok(Some(Frame::Prompt(payload))),
+            "password" => Ok(Some(Frame::Password(payload))),
+            "output" => Ok(Some(Frame::Output(payload))),
+            "end" => Ok(Some(Frame::End)),
+            "describe" => Ok(Some(Frame::Describe)),
+            "description" => Ok(Some(Frame::Description(decode_description(&payload)?))),
+            "complete" => Ok(Some(Frame::Complete(payload))),
+            "completions" => Ok(Some(Frame::Completions(
+                payload
+                    .lines()
+                    .map(|completion| completion.to_string())
+                    .collect(),
+            ))),
+            _ => Err(invalid_data("Unknown frame type")),
+        }
+    }
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, message)
+}
+
+/// Encode a scope description as lines of tab separated fields, the first line describes the
+/// scope and every following line describes a command. Mounted scopes and help sections follow as
+/// a line with their name and the number of lines of the nested description after it
+fn encode_description(description: &ScopeDescription) -> String {
+    let mut lines = vec![format!(
+        "scope\t{}",
+        encode_option(description.help_text().as_deref())
+    )];
+
+    for command in description.all_commands() {
+        let aliases: Vec<&str> = command.aliases().map(String::as_str).collect();
+
+        lines.push(format!(
+            "command\t{}\t{}\t{}\t{}",
+            escape(command.name()),
+            escape(&aliases.join(" ")),
+            encode_option(command.usage()),
+            encode_option(command.help_text().as_deref())
+        ));
+    }
+
+    let nested = description
+        .mounts()
+        .map(|(name, mount)| ("mount", name, mount))
+        .chain(
+            description
+                .sections()
+                .map(|(header, section)| ("section", header, section)),
+        );
+    for (kind, name, nested) in nested {
+        let encoded = encode_description(nested);
+        lines.push(format!(
+            "{}\t{}\t{}",
+            kind,
+            escape(name),
+            encoded.lines().count()
+        ));
+        lines.push(encoded);
+    }
+
+    lines.join("\n")
+}
+
+fn decode_description(payload: &str) -> io::Result<ScopeDescription> {
+    let lines: Vec<&str> = payload.lines().collect();
+    decode_lines(&lines)
+}
+
+fn decode_lines(lines: &[&str]) -> io::Result<ScopeDescription> {
+    let mut scope_help = None;
+    let mut commands = Vec::new();
+    let mut nested = Vec::new();
+    let mut index = 0;
+
+    while index < lines.len() {
+        let fields: Vec<&str> = lines[index].split('\t').collect();
+        index += 1;
+
+        match fields.as_slice() {
+            ["scope", help] => scope_help = decode_option(help),
+            ["command", name, aliases, usage, help] => {
+                let aliases = unescape(aliases)
+                    .split_whitespace()
+                    .map(|alias| alias.to_string())
+                    .collect();
+                let command =
+                    ScopeCmdDescription::new(unescape(name), aliases, decode_option(help));
+
+                commands.push(match decode_option(usage) {
+                    Some(usage) => command.with_usage(&usage),
+                    None => command,
+                });
+            }
+            [kind @ "mount", name, count] | [kind @ "section", name, count] => {
+                let end = count
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|count| index.checked_add(count))
+                    .filter(|end| *end <= lines.len())
+                    .ok_or_else(|| invalid_data("Invalid scope description"))?;
+
+                nested.push((*kind, unescape(name), decode_lines(&lines[index..end])?));
+                index = end;
+            }
+            _ => return Err(invalid_data("Invalid scope description")),
+        }
+    }
+
+    Ok(nested.into_iter().fold(
+        ScopeDescription::new(scope_help, commands),
+        |description, (kind, name, nested)| match kind {
+            "mount" => description.with_mounted_scope(&name, nested),
+            _ => description.with_section(&name, nested),
+        },
+    ))
+}
+
+/// Optional fields are empty when missing and start with a + when present
+fn encode_option(value: Option<&str>) -> String {
+    value.map_or_else(String::new, |value| format!("+{}", escape(value)))
+}
+
+fn decode_option(field: &str) -> Option<String> {
+    field.strip_prefix('+').map(unescape)
+}
+
+/// Escape backslashes, newlines and tabs so text fits in a single field
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('\n', "\\n")
+        .replace('\t', "\\t")
+}
+
+fn unescape(text: &str) -> String {
+    let mut unescaped = String::new();
+    let mut chars = text.chars();
+
+    while let Some(character) = chars.next() {
+        if character == '\\' {
+            match chars.next() {
+                Some('n') => unescaped.push('\n'),
+                Some('t') => unescaped.push('\t'),
+                Some(other) => unescaped.push(other),
+                None => unescaped.push('\\'),
+            }
+        } else {
+            unescaped.push(character);
+        }
+    }
+
+    unescaped
+}
+
+/// Reads lines from a client that speaks the framed protocol, prompts are sent as frames
+#[derive(Debug)]
+pub struct FramedLineReader<R: Read, W: Write> {
+    input: BufReader<R>,
+    output: W,
+    commands: ScopeDescription,
+}
+
+impl<R: Read, W: Write> FramedLineReader<R, W> {
+    /// Create a reader that reads frames from input and sends prompts to output
+    pub fn new(input: R, output: W) -> Self {
+        FramedLineReader {
+            input: BufReader::new(input),
+            output,
+            commands: ScopeDescription::new(None, Vec::new()),
+        }
+    }
+
+    fn send(&mut self, frame: Frame) -> Result<(), Error> {
+        Ok(frame.write_to(&mut self.output)?)
+    }
+
+    /// Send a prompt frame and wait for the line the user enters, answers requests from the
+    /// client while waiting
+    fn ask(&mut self, prompt: Frame) -> Result<String, Error> {
+        self.send(prompt)?;
+
+        loop {
+            match Frame::read_from(&mut self.input) {
+                Ok(Some(Frame::Line(line))) => return Ok(line),
+                Ok(Some(Frame::End)) | Ok(None) => return Err(Error::CtrlD),
+                Ok(Some(Frame::Describe)) => {
+                    self.send(Frame::Description(self.commands.clone()))?
+                }
+                Ok(Some(Frame::Complete(line))) => {
+                    let (_, completions) = complete_command(&self.commands, &line, line.len());
+                    self.send(Frame::Completions(completions))?
+                }
+                Ok(Some(_)) => (),
+                Err(error) => return Err(error.into()),
+            }
+        }
+    }
+}
+
+impl<R: Read, W: Write> LineReader for FramedLineReader<R, W> {
+    fn read_line(&mut self, prompt: &str) -> Result<String, Error> {
+        self.ask(Frame::Prompt(prompt.to_string()))
+    }
+
+    fn read_password(&mut self, prompt: &str) -> Result<String, Error> {
+        self.ask(Frame::Password(prompt.to_string()))
+    }
+
+    fn set_commands(&mut self, commands: &ScopeDescription) {
+        self.commands = commands.clone();
+    }
+}
+
+/// Output without a line end is sent once this many bytes are buffered
+const SEND_SIZE: usize = 8 * 1024;
+
+/// Sends output to a client that speaks the framed protocol. Complete lines are sent as soon as
+/// they are written, the rest of a line is sent when enough is buffered or the writer is flushed.
+/// Runners flush the writer before they show a prompt
+#[derive(Debug)]
+pub struct FramedLineWriter<W: Write> {
+    output: W,
+    buffer: String,
+}
+
+impl<W: Write> FramedLineWriter<W> {
+    /// Create a writer that sends output frames to output
+    pub fn new(output: W) -> Self {
+        FramedLineWriter {
+            output,
+            buffer: String::new(),
+        }
+    }
+
+    /// Send the first end bytes of the buffer as output frames
+    fn send(&mut self, end: usize) {
+        let output: String = self.buffer.drain(..end).collect();
+        let mut rest = output.as_str();
+
+        // Output that does not fit in a single frame is split on a character boundary
+        while !rest.is_empty() {
+            let mut end = rest.len().min(MAX_FRAME_SIZE);
+            while !rest.is_char_boundary(end) {
+                end -= 1;
+            }
+
+            let (chunk, next) = rest.split_at(end);
+            Frame::Output(chunk.to_string())
+                .write_to(&mut self.output)
+                .ok();
+            rest = next;
+        }
+    }
+}
+
+impl<W: Write> LineWriter for FramedLineWriter<W> {
+    fn write(&mut self, line: &str) {
+        self.buffer.push_str(line);
+
+        if let Some(end) = self.buffer.rfind('\n') {
+            self.send(end + 1);
+        } else if self.buffer.len() >= SEND_SIZE {
+            self.send(self.buffer.len());
+        }
+    }
+
+    fn flush(&mut self) {
+        self.send(self.buffer.len());
+        self.output.flush().ok();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frames_should_survive_a_round_trip() {
+        let frames = vec![
+            Frame::Prompt(">".to_string()),
+            Frame::Output("two\nlines\n".to_string()),
+            Frame::Line("greet Bob".to_string()),
+            Frame::End,
+        ];
+
+        let mut stream = Vec::new();
+        for frame in &frames {
+            frame.write_to(&mut stream).unwrap();
+        }
+
+        let mut input = &stream[..];
+        for frame in frames {
+            assert_eq!(Frame::read_from(&mut input).unwrap(), Some(frame));
+        }
+        assert_eq!(Frame::read_from(&mut input).unwrap(), None);
+    }
+
+    #[test]
+    fn frame_should_be_encoded_with_header() {
+        let mut stream = Vec::new();
+        Frame::Prompt(">".to_string())
+            .write_to(&mut stream)
+            .unwrap();
+
+        assert_eq!(stream, b"prompt 1\n>");
+    }
+
+    fn get_test_description() -> ScopeDescription {
+        ScopeDescription::new(
+            Some("Scope\thelp\nwith \\ lines".to_string()),
+            vec![
+                ScopeCmdDescription::new(
+                    "greet".to_string(),
+                    vec!["hi".to_string(), "hello".to_string()],
+                    Some("Greet someone".to_string()),
+                )
+                .with_usage("<name>"),
+                ScopeCmdDescription::new("quit".to_string(), vec![], None),
+            ],
+        )
+    }
+
+    #[test]
+    fn description_with_mounts_and_sections_should_survive_a_round_trip() {
+        let description = ScopeDescription::new(None, vec![])
+            .with_mount(
+                "db",
+                ScopeDescription::new(
+                    Some("Database\tcommands".to_string()),
+                    vec![ScopeCmdDescription::new(
+                        "query".to_string(),
+                        vec![],
+                        Some("Run a query\n  with indentation".to_string()),
+                    )],
+                )
+                .with_mount("admin", get_test_description()),
+            )
+            .with_section("Global commands:", get_test_description());
+        let mut stream = Vec::new();
+        Frame::Description(description.clone())
+            .write_to(&mut stream)
+            .unwrap();
+
+        assert_eq!(
+            Frame::read_from(&mut &stream[..]).unwrap(),
+            Some(Frame::Description(description))
+        );
+    }
+
+    #[test]
+    fn should_reject_nested_description_that_is_too_long() {
+        assert!(decode_description("scope\t\nmount\tdb\t5\nscope\t").is_err());
+    }
+
+    #[test]
+    fn description_should_survive_a_round_trip() {
+        let mut stream = Vec::new();
+        Frame::Description(get_test_description())
+            .write_to(&mut stream)
+            .unwrap();
+
+        assert_eq!(
+            Frame::read_from(&mut &stream[..]).unwrap(),
+            Some(Frame::Description(get_test_description()))
+        );
+    }
+
+    #[test]
+    fn reader_should_answer_describe_and_complete() {
+        let mut input = Vec::new();
+        Frame::Describe.write_to(&mut input).unwrap();
+        Frame::Complete("gr".to_string())
+            .write_to(&mut input)
+            .unwrap();
+        let mut output = Vec::new();
+
+        let mut reader = FramedLineReader::new(&input[..], &mut output);
+        reader.set_commands(&get_test_description());
+        assert_eq!(reader.read_line(">"), Err(Error::CtrlD));
+
+        let mut frames = &output[..];
+        assert_eq!(
+            Frame::read_from(&mut frames).unwrap(),
+            Some(Frame::Prompt(">".to_string()))
+        );
+        assert_eq!(
+            Frame::read_from(&mut frames).unwrap(),
+            Some(Frame::Description(get_test_description()))
+        );
+        assert_eq!(
+            Frame::read_from(&mut frames).unwrap(),
+            Some(Frame::Completions(vec!["greet".to_string()]))
+        );
+    }
+
+    #[test]
+    fn should_reject_frames_that_are_too_long() {
+        let header = format!("output {}\n", MAX_FRAME_SIZE + 1);
+        let error = Frame::read_from(&mut header.as_bytes()).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+
+        let error = Frame::Output("x".repeat(MAX_FRAME_SIZE + 1))
+            .write_to(&mut Vec::new())
+            .unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn should_reject_header_that_is_too_long() {
+        let header = "x".repeat(1000);
+        let error = Frame::read_from(&mut header.as_bytes()).unwrap_err();
+
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn writer_should_split_long_output_into_frames() {
+        let mut output = Vec::new();
+        let mut writer = FramedLineWriter::new(&mut output);
+
+        writer.write(&"é".repeat(MAX_FRAME_SIZE / 2 + 1));
+        writer.flush();
+
+        let mut input = &output[..];
+        let mut text = String::new();
+        while let Some(Frame::Output(chunk)) = Frame::read_from(&mut input).unwrap() {
+            assert!(chunk.len() <= MAX_FRAME_SIZE);
+            text.push_str(&chunk);
+        }
+        assert_eq!(text, "é".repeat(MAX_FRAME_SIZE / 2 + 1));
+    }
+
+    #[test]
+    fn should_reject_unknown_frame() {
+        assert!(Frame::read_from(&mut &b"shout 2\nhi"[..]).is_err());
+    }
+
+    #[test]
+    fn reader_should_send_prompt_and_read_line() {
+        let mut input = Vec::new();
+        Frame::Line("greet".to_string())
+            .write_to(&mut input)
+            .unwrap();
+        let mut output = Vec::new();
+
+        let mut reader = FramedLineReader::new(&input[..], &mut output);
+
+        assert_eq!(reader.read_line(">"), Ok("greet".to_string()));
+        assert_eq!(reader.read_line(">"), Err(Error::CtrlD));
+        assert_eq!(output, b"prompt 1\n>prompt 1\n>");
+    }
+
+    #[test]
+    fn writer_should_send_complete_lines_right_away() {
+        let mut output = Vec::new();
+        let mut writer = FramedLineWriter::new(&mut output);
+
+        writer.write_line("one");
+        writer.write("tw");
+        writer.write("o\nthr");
+
+        assert_eq!(output, b"output 4\none\noutput 4\ntwo\n");
+    }
+
+    #[test]
+    fn writer_should_send_rest_of_line_when_flushed() {
+        let mut output = Vec::new();
+        let mut writer = FramedLineWriter::new(&mut output);
+
+        writer.write("Name: ");
+        writer.flush();
+        writer.flush();
+
+        assert_eq!(output, b"output 6\nName: ");
+    }
+
+    #[test]
+    fn writer_should_send_long_line_without_flush() {
+        let mut output = Vec::new();
+        let mut writer = FramedLineWriter::new(&mut output);
+
+        writer.write(&"a".repeat(SEND_SIZE));
+
+        assert!(output.starts_with(format!("output {}\n", SEND_SIZE).as_bytes()));
+    }
+}
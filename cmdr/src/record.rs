@@ -0,0 +1,255 @@
+//! Structured output records, commands write these to a LineWriter to produce output that can be
+//! rendered for humans or as JSON lines for scripts
+use crate::format::{format_list, format_table};
+use std::fmt::{Display, Error as FmtError, Formatter};
+
+/// A single structured output record, an ordered list of named values
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Record {
+    fields: Vec<(String, Value)>,
+}
+
+impl Record {
+    /// Construct a new empty record
+    pub fn new() -> Self {
+        Record { fields: Vec::new() }
+    }
+
+    /// Add a named value to the record
+    pub fn field<V: Into<Value>>(mut self, name: &str, value: V) -> Self {
+        self.fields.push((name.to_string(), value.into()));
+        self
+    }
+
+    /// An iterator of all named values in this record in the order they were added
+    pub fn fields(&self) -> impl Iterator<Item = (&str, &Value)> {
+        self.fields
+            .iter()
+            .map(|(name, value)| (name.as_ref(), value))
+    }
+
+    /// Get a value by its name
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        self.fields
+            .iter()
+            .find(|(field, _)| field == name)
+            .map(|(_, value)| value)
+    }
+
+    /// Render the record as a single line json object
+    pub fn to_json(&self) -> String {
+        let fields: Vec<String> = self
+            .fields
+            .iter()
+            .map(|(name, value)| format!("{}:{}", json_string(name), value.to_json()))
+            .collect();
+
+        format!("{{{}}}", fields.join(","))
+    }
+}
+
+/// Render a record as a list of aligned names and values
+pub(crate) fn format_record(record: &Record, width: usize) -> String {
+    let values: Vec<(&str, String)> = record
+        .fields()
+        .map(|(name, value)| (name, value.to_string()))
+        .collect();
+
+    let items: Vec<(&str, &str)> = values
+        .iter()
+        .map(|(name, value)| (*name, value.as_ref()))
+        .collect();
+
+    format_list(&items, width)
+}
+
+/// Render a list of records as a table with a header containing the field names of the first
+/// record
+pub(crate) fn format_records(records: &[Record], width: usize) -> String {
+    let headers: Vec<&str> = match records.first() {
+        Some(record) => record.fields().map(|(name, _)| name).collect(),
+        None => return String::new(),
+    };
+
+    let rows: Vec<Vec<String>> = records
+        .iter()
+        .map(|record| {
+            headers
+                .iter()
+                .map(|name| record.get(name).map(Value::to_string).unwrap_or_default())
+                .collect()
+        })
+        .collect();
+
+    format_table(&headers, &rows, width)
+}
+
+/// A single value in a record
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    /// A text value
+    Text(String),
+
+    /// An integer value
+    Integer(i64),
+
+    /// A floating point value
+    Float(f64),
+
+    /// A boolean value
+    Bool(bool),
+
+    /// No value
+    Null,
+}
+
+impl Value {
+    fn to_json(&self) -> String {
+        match self {
+            Value::Text(text) => json_string(text),
+            Value::Float(float) if !float.is_finite() => "null".to_string(),
+            Value::Null => "null".to_string(),
+            other => other.to_string(),
+        }
+    }
+}
+
+impl Display for Value {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> Result<(), FmtError> {
+        match self {
+            Value::Text(text) => formatter.pad(text),
+            Value::Integer(integer) => formatter.pad(&integer.to_string()),
+            Value::Float(float) => formatter.pad(&float.to_string()),
+            Value::Bool(boolean) => formatter.pad(&boolean.to_string()),
+            Value::Null => formatter.pad(""),
+        }
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Value::Text(value.to_string())
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Value::Text(value)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Value::Bool(value)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(value: f64) -> Self {
+        Value::Float(value)
+    }
+}
+
+impl<V: Into<Value>> From<Option<V>> for Value {
+    fn from(value: Option<V>) -> Self {
+        value.map_or(Value::Null, Into::into)
+    }
+}
+
+impl From<i64> for Value {
+    fn from(value: i64) -> Self {
+        Value::Integer(value)
+    }
+}
+
+macro_rules! integer_value {
+    ($($integer:ty),*) => {
+        $(
+            impl From<$integer> for Value {
+                fn from(value: $integer) -> Self {
+                    Value::Integer(value as i64)
+                }
+            }
+        )*
+    };
+}
+
+integer_value!(i8, i16, i32, u8, u16, u32, isize, usize);
+
+/// Quote and escape a string for use in json
+pub(crate) fn json_string(value: &str) -> String {
+    let mut result = String::with_capacity(value.len() + 2);
+    result.push('"');
+
+    for character in value.chars() {
+        match character {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            '\t' => result.push_str("\\t"),
+            control if (control as u32) < 0x20 => {
+                result.push_str(&format!("\\u{:04x}", control as u32))
+            }
+            other => result.push(other),
+        }
+    }
+
+    result.push('"');
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_test_record() -> Record {
+        Record::new()
+            .field("name", "Bob")
+            .field("age", 42)
+            .field("admin", false)
+    }
+
+    #[test]
+    fn record_should_render_json_line() {
+        assert_eq!(
+            get_test_record().to_json(),
+            r#"{"name":"Bob","age":42,"admin":false}"#
+        );
+    }
+
+    #[test]
+    fn record_should_escape_json_strings() {
+        let record = Record::new().field("quote\"", "line\nbreak\\");
+
+        assert_eq!(record.to_json(), r#"{"quote\"":"line\nbreak\\"}"#);
+    }
+
+    #[test]
+    fn record_should_render_missing_values_as_null() {
+        let record = Record::new().field("value", None::<i32>);
+
+        assert_eq!(record.to_json(), r#"{"value":null}"#);
+    }
+
+    #[test]
+    fn record_should_render_aligned_names_and_values() {
+        assert_eq!(
+            format_record(&get_test_record(), 80),
+            "name   Bob\nage    42\nadmin  false"
+        );
+    }
+
+    #[test]
+    fn records_should_render_as_table() {
+        let records = vec![
+            get_test_record(),
+            Record::new().field("name", "Alice").field("age", 7),
+        ];
+
+        assert_eq!(
+            format_records(&records, 80),
+            "name   age  admin\nBob    42   false\nAlice  7"
+        );
+    }
+}
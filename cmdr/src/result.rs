@@ -1,6 +1,14 @@
+use crate::cancel::CancelToken;
+use crate::jobs::Job;
+use crate::messages::Messages;
 use crate::Scope;
-use std::fmt::Debug;
+use rustyline::error::ReadlineError;
+use std::error::Error as StdError;
+use std::fmt::{Debug, Display, Formatter};
+use std::io;
+use std::num::{ParseFloatError, ParseIntError};
 use std::result::Result as StdResult;
+use std::str::ParseBoolError;
 
 /// Default cmdr Result type
 pub type Result<T> = StdResult<T, Error>;
@@ -19,6 +27,10 @@ pub enum Action {
     /// Switch to a sub scope,
     SubScope(Box<dyn Scope>),
 
+    /// Start a job in the background and go on to the next command. The first job turns on the
+    /// built-in jobs, wait and kill commands
+    Background(Job),
+
     /// Result Exit, exit the current scope and return to the parent scope if available
     Exit,
 
@@ -32,6 +44,7 @@ impl Debug for Action {
             Action::Done => formatter.debug_tuple("Done").finish(),
             Action::NewScope(_) => formatter.debug_tuple("NewScope").finish(),
             Action::SubScope(_) => formatter.debug_tuple("SubScope").finish(),
+            Action::Background(job) => formatter.debug_tuple("Background").field(job).finish(),
             Action::Exit => formatter.debug_tuple("Exit").finish(),
             Action::Quit => formatter.debug_tuple("Quit").finish(),
         }
@@ -50,10 +63,19 @@ impl Action {
     pub fn sub_scope<S: Scope + 'static>(scope: S) -> CommandResult {
         CommandResult::Ok(Action::SubScope(Box::new(scope)))
     }
+
+    /// Shortcut to construct a Background action to return from a command
+    /// This runs work on a separate thread, the user can enter other commands while it runs
+    pub fn background<F>(name: &str, work: F) -> CommandResult
+    where
+        F: FnOnce(CancelToken) -> Result<String> + Send + 'static,
+    {
+        CommandResult::Ok(Action::Background(Job::new(name, work)))
+    }
 }
 
 /// Specifies an error while parsing or executing a command
-#[derive(Debug, PartialEq)]
+#[derive(Debug)]
 pub enum Error {
     /// Invalid command was entered
     InvalidCommand(String),
@@ -67,15 +89,345 @@ pub enum Error {
     /// An unknown error occured reading a line
     LineReaderError,
 
+    /// An async command was run by a runner that can not run async commands
+    AsyncCommand(String),
+
+    /// A command needs a context of this type but the runner has no such context
+    NoContext(String),
+
     /// An empty line was read
     EmptyLine,
 
     /// Control C was pressed
     CtrlC,
 
+    /// The command was cancelled, usually because Ctrl-C was pressed while it was running
+    Cancelled,
+
     /// Control D was pressed
     CtrlD,
 
     /// Fatal error, quit the application with an error code
     Fatal(i32),
+
+    /// Reading input or writing output failed. Io errors are equal when their kinds and messages
+    /// are equal
+    Io(io::Error),
+
+    /// An error returned by the application. Create one with `Error::custom`, convert a result
+    /// with `ResultExt::custom` or convert a boxed error with `?`. Custom errors are equal when their messages are equal, `==` does not
+    /// compare their types, use `downcast_ref` for that
+    Custom(Box<dyn StdError + Send + Sync>),
+}
+
+impl Error {
+    /// Wrap an application error so it can be returned from a command
+    pub fn custom<E: Into<Box<dyn StdError + Send + Sync>>>(error: E) -> Self {
+        Error::Custom(error.into())
+    }
+
+    /// The exit code for the process when this error ends the application. Fatal errors exit
+    /// with their own code, all other errors with 1
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Error::Fatal(code) => *code,
+            _ => 1,
+        }
+    }
+
+    /// The error message followed by the messages of its causes, one per line
+    pub(crate) fn report(&self, messages: &dyn Messages) -> String {
+        let mut report = messages.application_error(&self.to_string());
+
+        // Io and custom errors show the message of the error they wrap, skip it as a cause
+        let mut source = match self {
+            Error::Io(_) | Error::Custom(_) => self.source().and_then(StdError::source),
+            _ => self.source(),
+        };
+        while let Some(cause) = source {
+            report.push('\n');
+            report.push_str(&messages.caused_by(&cause.to_string()));
+            source = cause.source();
+        }
+        report
+    }
+
+    /// Return the application error if this is a custom error of type `E`
+    pub fn downcast_ref<E: StdError + 'static>(&self) -> Option<&E> {
+        match self {
+            Error::Custom(error) => error.downcast_ref(),
+            _ => None,
+        }
+    }
+}
+
+/// Io and custom errors are compared by their messages only, see `Error::Custom`
+impl PartialEq for Error {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Error::InvalidCommand(command), Error::InvalidCommand(other)) => command == other,
+            (Error::InvalidNumberOfArguments(command), Error::InvalidNumberOfArguments(other)) => {
+                command == other
+            }
+            (Error::NoHelpForCommand(command), Error::NoHelpForCommand(other)) => command == other,
+            (Error::LineReaderError, Error::LineReaderError) => true,
+            (Error::AsyncCommand(command), Error::AsyncCommand(other)) => command == other,
+            (Error::NoContext(context), Error::NoContext(other)) => context == other,
+            (Error::EmptyLine, Error::EmptyLine) => true,
+            (Error::CtrlC, Error::CtrlC) => true,
+            (Error::Cancelled, Error::Cancelled) => true,
+            (Error::CtrlD, Error::CtrlD) => true,
+            (Error::Fatal(code), Error::Fatal(other)) => code == other,
+            (Error::Io(error), Error::Io(other)) => {
+                error.kind() == other.kind() && error.to_string() == other.to_string()
+            }
+            (Error::Custom(error), Error::Custom(other)) => error.to_string() == other.to_string(),
+            _ => false,
+        }
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::InvalidCommand(command) => write!(formatter, "Unknown command: {}", command),
+            Error::InvalidNumberOfArguments(command) => write!(
+                formatter,
+                "Invalid number of arguments for command: {}",
+                command
+            ),
+            Error::NoHelpForCommand(command) => {
+                write!(formatter, "No help available for command: {}", command)
+            }
+            Error::LineReaderError => write!(formatter, "Unable to read a line"),
+            Error::AsyncCommand(command) => write!(
+                formatter,
+                "Async command can not be run by this runner: {}",
+                command
+            ),
+            Error::NoContext(context) => {
+                write!(formatter, "No context of type {} for this command", context)
+            }
+            Error::EmptyLine => write!(formatter, "Empty line"),
+            Error::CtrlC => write!(formatter, "Interrupted"),
+            Error::Cancelled => write!(formatter, "Cancelled"),
+            Error::CtrlD => write!(formatter, "End of input"),
+            Error::Fatal(code) => write!(formatter, "Fatal error with exit code {}", code),
+            Error::Io(error) => Display::fmt(error, formatter),
+            Error::Custom(error) => Display::fmt(error, formatter),
+        }
+    }
+}
+
+/// The source of io and custom errors is the error they wrap
+impl StdError for Error {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match self {
+            Error::Io(error) => Some(error),
+            Error::Custom(error) => Some(&**error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Self {
+        Error::Io(error)
+    }
+}
+
+impl From<ReadlineError> for Error {
+    fn from(error: ReadlineError) -> Self {
+        match error {
+            ReadlineError::Interrupted => Error::CtrlC,
+            ReadlineError::Eof => Error::CtrlD,
+            ReadlineError::Io(error) => Error::Io(error),
+            error => Error::custom(error),
+        }
+    }
+}
+
+impl From<Box<dyn StdError + Send + Sync>> for Error {
+    fn from(error: Box<dyn StdError + Send + Sync>) -> Self {
+        Error::Custom(error)
+    }
+}
+
+impl From<String> for Error {
+    fn from(message: String) -> Self {
+        Error::custom(message)
+    }
+}
+
+impl From<&str> for Error {
+    fn from(message: &str) -> Self {
+        Error::custom(message)
+    }
+}
+
+impl From<ParseIntError> for Error {
+    fn from(error: ParseIntError) -> Self {
+        Error::custom(error)
+    }
+}
+
+impl From<ParseFloatError> for Error {
+    fn from(error: ParseFloatError) -> Self {
+        Error::custom(error)
+    }
+}
+
+impl From<ParseBoolError> for Error {
+    fn from(error: ParseBoolError) -> Self {
+        Error::custom(error)
+    }
+}
+
+/// Converts the errors of the application to `Error::Custom`. cmdr can not convert every error
+/// type with `?`, call `custom()` on the result first:
+///
+/// ```
+/// use cmdr::{Result, ResultExt};
+///
+/// fn home() -> Result<String> {
+///     std::env::var("HOME").custom()
+/// }
+/// ```
+pub trait ResultExt<T> {
+    /// Wrap the error in `Error::Custom`, the value is kept as it is
+    fn custom(self) -> Result<T>;
+}
+
+impl<T, E: Into<Box<dyn StdError + Send + Sync>>> ResultExt<T> for StdResult<T, E> {
+    fn custom(self) -> Result<T> {
+        self.map_err(Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::messages::DefaultMessages;
+    use std::fmt::{Display, Formatter};
+
+    #[derive(Debug)]
+    struct AppError;
+
+    impl Display for AppError {
+        fn fmt(&self, formatter: &mut Formatter<'_>) -> std::fmt::Result {
+            write!(formatter, "Something went wrong")
+        }
+    }
+
+    impl StdError for AppError {}
+
+    fn parse(number: &str) -> Result<u32> {
+        Ok(number.parse::<u32>()?)
+    }
+
+    #[test]
+    fn should_convert_with_question_mark() {
+        assert_eq!(parse("12"), Ok(12));
+        assert_eq!(
+            parse("twelve"),
+            Err(Error::custom("invalid digit found in string"))
+        );
+    }
+
+    #[test]
+    fn should_downcast_custom_error() {
+        let error = Error::custom(AppError);
+
+        assert!(error.downcast_ref::<AppError>().is_some());
+        assert!(error.downcast_ref::<ParseIntError>().is_none());
+        assert!(Error::CtrlC.downcast_ref::<AppError>().is_none());
+    }
+
+    #[derive(Debug)]
+    struct WrappingError(AppError);
+
+    impl Display for WrappingError {
+        fn fmt(&self, formatter: &mut Formatter<'_>) -> std::fmt::Result {
+            write!(formatter, "Unable to greet")
+        }
+    }
+
+    impl StdError for WrappingError {
+        fn source(&self) -> Option<&(dyn StdError + 'static)> {
+            Some(&self.0)
+        }
+    }
+
+    #[test]
+    fn should_display_messages() {
+        assert_eq!(
+            Error::InvalidCommand("greet".to_string()).to_string(),
+            "Unknown command: greet"
+        );
+        assert_eq!(Error::custom(AppError).to_string(), "Something went wrong");
+    }
+
+    #[test]
+    fn should_return_wrapped_error_as_source() {
+        let error = Error::custom(WrappingError(AppError));
+        let source = error.source().unwrap();
+
+        assert_eq!(error.to_string(), "Unable to greet");
+        assert!(source.downcast_ref::<WrappingError>().is_some());
+        assert_eq!(source.source().unwrap().to_string(), "Something went wrong");
+        assert!(Error::from(io::Error::from(io::ErrorKind::NotFound))
+            .source()
+            .unwrap()
+            .is::<io::Error>());
+        assert!(Error::CtrlC.source().is_none());
+    }
+
+    #[test]
+    fn should_report_error_with_causes() {
+        assert_eq!(
+            Error::custom(WrappingError(AppError)).report(&DefaultMessages),
+            "Error: Unable to greet\nCaused by: Something went wrong"
+        );
+    }
+
+    #[test]
+    fn should_exit_with_fatal_error_code() {
+        assert_eq!(Error::Fatal(101).exit_code(), 101);
+        assert_eq!(Error::custom(AppError).exit_code(), 1);
+    }
+
+    #[test]
+    fn should_keep_io_errors() {
+        let error = Error::from(ReadlineError::Io(io::Error::new(
+            io::ErrorKind::BrokenPipe,
+            "Pipe closed",
+        )));
+
+        assert!(matches!(&error, Error::Io(error) if error.kind() == io::ErrorKind::BrokenPipe));
+        assert_eq!(Error::from(ReadlineError::Eof), Error::CtrlD);
+        assert_eq!(Error::from(ReadlineError::Interrupted), Error::CtrlC);
+    }
+
+    #[test]
+    fn should_wrap_errors_of_results() {
+        let error: StdResult<u32, AppError> = Err(AppError);
+
+        assert_eq!(error.custom(), Err(Error::custom("Something went wrong")));
+        assert_eq!(Ok::<_, AppError>(12).custom(), Ok(12));
+        assert!(Err::<u32, _>(AppError)
+            .custom()
+            .unwrap_err()
+            .downcast_ref::<AppError>()
+            .is_some());
+    }
+
+    #[test]
+    fn should_compare_custom_errors_by_message() {
+        assert_eq!(
+            Error::custom(AppError),
+            Error::custom("Something went wrong")
+        );
+        assert_ne!(Error::custom(AppError), Error::custom("Something else"));
+        assert_ne!(Error::custom(AppError), Error::CtrlC);
+    }
 }
@@ -0,0 +1,372 @@
+//! The command loop that `Runner` and `AsyncRunner` share, they only differ in how lines are read
+//! and how commands are run
+use crate::cancel::CancelToken;
+use crate::context::Context;
+use crate::description::{ScopeCmdDescription, ScopeDescription};
+use crate::future::LocalBoxFuture;
+use crate::global::GlobalScope;
+use crate::interaction::Interaction;
+use crate::jobs::Jobs;
+use crate::line::Line;
+use crate::line_reader::LineReader;
+use crate::line_writer::{JsonWriter, LineWriter};
+use crate::messages::{DefaultMessages, Messages};
+use crate::middleware::{Middleware, Next, RunCommand};
+use crate::result::{Action, CommandResult, Error, Result};
+use crate::scope::Scope;
+use std::any::Any;
+use std::sync::Arc;
+
+/// How a runner reads lines and runs commands
+pub(crate) trait Input {
+    /// Read the next line that is entered
+    fn read_line<'a>(&'a mut self, prompt: &'a str) -> LocalBoxFuture<'a, Result<String>>;
+
+    /// Pass the commands of the running scope to the reader for highlighting and hints
+    fn set_commands(&mut self, commands: &ScopeDescription);
+
+    /// The reader for questions that commands ask through their Interaction
+    fn line_reader(&mut self) -> &mut dyn LineReader;
+
+    /// Run a command of scope, catch_interrupt is set when Ctrl-C should cancel the command
+    fn run_command<'a>(
+        scope: &'a mut dyn Scope,
+        command: &'a ScopeCmdDescription,
+        args: &'a [String],
+        interaction: &'a mut Interaction<'_>,
+        catch_interrupt: bool,
+    ) -> LocalBoxFuture<'a, CommandResult>;
+}
+
+/// Reads lines from a blocking LineReader and runs commands on the current thread
+#[derive(Debug)]
+pub(crate) struct BlockingInput<R: LineReader> {
+    pub(crate) reader: R,
+}
+
+impl<R: LineReader> Input for BlockingInput<R> {
+    fn read_line<'a>(&'a mut self, prompt: &'a str) -> LocalBoxFuture<'a, Result<String>> {
+        Box::pin(async move { self.reader.read_line(prompt) })
+    }
+
+    fn set_commands(&mut self, commands: &ScopeDescription) {
+        self.reader.set_commands(commands)
+    }
+
+    fn line_reader(&mut self) -> &mut dyn LineReader {
+        &mut self.reader
+    }
+
+    fn run_command<'a>(
+        scope: &'a mut dyn Scope,
+        command: &'a ScopeCmdDescription,
+        args: &'a [String],
+        interaction: &'a mut Interaction<'_>,
+        catch_interrupt: bool,
+    ) -> LocalBoxFuture<'a, CommandResult> {
+        Box::pin(async move {
+            let _interrupt = if catch_interrupt {
+                Some(interaction.cancel_token().cancel_on_interrupt())
+            } else {
+                None
+            };
+            scope.run_command(command, args, interaction)
+        })
+    }
+}
+
+/// The command loop with the settings of a runner
+#[derive(Debug)]
+pub(crate) struct RunLoop<I: Input, W: LineWriter> {
+    pub(crate) input: I,
+    pub(crate) writer: W,
+    pub(crate) quit_on_ctrl_c: bool,
+    pub(crate) catch_interrupt: bool,
+    pub(crate) json_output: bool,
+    pub(crate) messages: Arc<dyn Messages>,
+    pub(crate) global: Option<GlobalScope>,
+    pub(crate) context: Option<Box<dyn Any>>,
+    pub(crate) middleware: Vec<Box<dyn Middleware>>,
+    pub(crate) jobs: Jobs,
+}
+
+impl<I: Input, W: LineWriter> RunLoop<I, W> {
+    pub(crate) fn new(input: I, writer: W) -> Self {
+        RunLoop {
+            input,
+            writer,
+            quit_on_ctrl_c: true,
+            catch_interrupt: true,
+            json_output: false,
+            messages: Arc::new(DefaultMessages),
+            global: None,
+            context: None,
+            middleware: Vec::new(),
+            jobs: Jobs::new(),
+        }
+    }
+
+    /// Show messages that can be shared with other runners
+    pub(crate) fn set_messages(&mut self, messages: Arc<dyn Messages>) {
+        self.jobs.set_messages(messages.clone());
+        self.writer.set_messages(messages.clone());
+        self.messages = messages;
+    }
+
+    /// Set whether Ctrl-C in the terminal cancels running commands and waiting for jobs
+    pub(crate) fn set_catch_interrupt(&mut self, catch: bool) {
+        self.catch_interrupt = catch;
+        self.jobs.set_catch_interrupt(catch);
+    }
+
+    pub(crate) fn set_context<T: Any>(&mut self, context: T) {
+        self.context = Some(Box::new(Context::new(context)));
+    }
+
+    pub(crate) fn set_global_scope(&mut self, scope: Box<dyn Scope>) {
+        self.global = Some(GlobalScope::new(scope));
+    }
+
+    /// Run scope and the scopes it switches to until the user quits
+    pub(crate) fn run<'a>(
+        &'a mut self,
+        scope: &'a mut dyn Scope,
+    ) -> LocalBoxFuture<'a, CommandResult> {
+        Box::pin(async move {
+            let mut result = self.run_scope(scope).await;
+
+            while let Ok(Action::NewScope(mut sub_scope)) = result {
+                result = self.run_scope(sub_scope.as_mut()).await;
+            }
+
+            result
+        })
+    }
+
+    /// Run like `run` and return the exit code for the process, errors other than fatal errors
+    /// are printed to stderr
+    pub(crate) async fn run_main<F>(&mut self, scope: &mut dyn Scope, exit_code: F) -> i32
+    where
+        F: FnOnce(&Error) -> i32,
+    {
+        match self.run(scope).await {
+            Ok(_) => 0,
+            Err(error) => {
+                if !matches!(error, Error::Fatal(_)) {
+                    eprintln!("{}", error.report(&*self.messages));
+                }
+                exit_code(&error)
+            }
+        }
+    }
+
+    /// Execute commands in this scope until a command returns an action other than Done
+    fn run_scope<'a>(&'a mut self, scope: &'a mut dyn Scope) -> LocalBoxFuture<'a, CommandResult> {
+        Box::pin(async move {
+            scope.before_loop();
+
+            let mut last_result = Ok(Action::Done);
+
+            while let Ok(Action::Done) = last_result {
+                self.jobs.report_finished(&mut self.writer);
+                self.writer.flush();
+                // Read again for every line, commands can change while the scope runs
+                let commands = self.all_commands(scope);
+                self.input.set_commands(&commands);
+
+                last_result = match self.input.read_line(scope.prompt().as_ref()).await {
+                    Err(Error::CtrlC) if !self.quit_on_ctrl_c => Ok(Action::Done),
+                    Err(error) => Err(error),
+                    Ok(line) => match Line::try_parse(line.as_ref()) {
+                        Err(error) => Err(error),
+                        Ok(line) => {
+                            let line = scope.before_command(line);
+                            let result = match self.run_line(scope, &line).await {
+                                Ok(Action::SubScope(mut sub_scope)) => {
+                                    self.run_scope(sub_scope.as_mut()).await
+                                }
+                                result => result,
+                            };
+
+                            scope.after_command(&line, result)
+                        }
+                    },
+                };
+
+                if let Err(error) = last_result {
+                    last_result = scope.handle_error_with(
+                        error,
+                        &mut Interaction::new(self.input.line_reader(), &mut self.writer)
+                            .with_messages(&*self.messages),
+                    )
+                }
+
+                self.writer.flush();
+            }
+
+            scope.after_loop();
+
+            match last_result {
+                Ok(Action::Exit) => Ok(Action::Done),
+                _ => last_result,
+            }
+        })
+    }
+
+    /// Commands of the scope and the commands of the runner, for highlighting and hints
+    fn all_commands(&self, scope: &dyn Scope) -> ScopeDescription {
+        self.help_sections()
+            .into_iter()
+            .fold(scope.commands(), |commands, (_, section)| {
+                commands.with_extra_commands(section.all_commands())
+            })
+    }
+
+    /// Sections that help lists next to the commands of every scope
+    pub(crate) fn help_sections(&self) -> Vec<(String, ScopeDescription)> {
+        self.global
+            .iter()
+            .map(|global| (self.messages.global_help_header(), global.commands()))
+            .chain(if self.jobs.enabled() {
+                Some((self.messages.jobs_help_header(), self.jobs.commands()))
+            } else {
+                None
+            })
+            .collect()
+    }
+
+    /// Run a line in scope: commands of the scope come first, then global commands, then the job
+    /// commands and finally `Scope::default`. Background jobs are started, other actions and
+    /// errors are returned to the caller. The hooks of the scope are not called
+    pub(crate) fn run_line<'a>(
+        &'a mut self,
+        scope: &'a mut dyn Scope,
+        line: &'a Line,
+    ) -> LocalBoxFuture<'a, CommandResult> {
+        Box::pin(async move {
+            let sections = self.help_sections();
+            let scope_commands = scope.commands();
+            let global_commands = self.global.as_ref().map(GlobalScope::commands);
+            let global_command = global_commands
+                .as_ref()
+                .and_then(|global| global.command_for_line(line));
+
+            let result = match scope_commands.command_for_line(line) {
+                Some(command) => self.run_command(scope, command, line, &sections).await,
+                None => match global_command {
+                    Some(command) => self.run_global_command(command, line, &sections).await,
+                    None => match self.jobs.run_line(line, &mut self.writer) {
+                        Some(result) => result,
+                        None => scope.default(line),
+                    },
+                },
+            };
+
+            match result {
+                Ok(Action::Background(job)) => {
+                    self.jobs.start(job, &mut self.writer);
+                    Ok(Action::Done)
+                }
+                result => result,
+            }
+        })
+    }
+
+    /// Run a command of the global scope
+    async fn run_global_command(
+        &mut self,
+        command: &ScopeCmdDescription,
+        line: &Line,
+        sections: &[(String, ScopeDescription)],
+    ) -> CommandResult {
+        // The global scope is taken out while its command runs so the runner can be borrowed
+        let mut global = self.global.take();
+        let result = match global.as_mut() {
+            Some(global) => {
+                self.run_command(global.scope_mut(), command, line, sections)
+                    .await
+            }
+            None => Err(Error::InvalidCommand(command.name().to_string())),
+        };
+        self.global = global;
+        result
+    }
+
+    /// Run a single command through the middleware
+    async fn run_command(
+        &mut self,
+        scope: &mut dyn Scope,
+        command: &ScopeCmdDescription,
+        line: &Line,
+        sections: &[(String, ScopeDescription)],
+    ) -> CommandResult {
+        let mut run = ScopeCommand::<I> {
+            scope,
+            command,
+            args: &line.args,
+            input: &mut self.input,
+            context: &mut self.context,
+            messages: &*self.messages,
+            sections,
+            catch_interrupt: self.catch_interrupt,
+        };
+
+        let mut json_writer;
+        let writer: &mut dyn LineWriter = if self.json_output {
+            json_writer = JsonWriter::new(&mut self.writer);
+            &mut json_writer
+        } else {
+            &mut self.writer
+        };
+
+        Next::new(line, command, &mut self.middleware, writer, &mut run)
+            .run()
+            .await
+    }
+}
+
+/// A command of a scope at the end of the middleware chain
+struct ScopeCommand<'a, I: Input> {
+    scope: &'a mut dyn Scope,
+    command: &'a ScopeCmdDescription,
+    args: &'a [String],
+    input: &'a mut I,
+    context: &'a mut Option<Box<dyn Any>>,
+    messages: &'a dyn Messages,
+    sections: &'a [(String, ScopeDescription)],
+    catch_interrupt: bool,
+}
+
+impl<I: Input> RunCommand for ScopeCommand<'_, I> {
+    fn run<'a>(&'a mut self, writer: &'a mut dyn LineWriter) -> LocalBoxFuture<'a, CommandResult> {
+        let ScopeCommand {
+            scope,
+            command,
+            args,
+            input,
+            context,
+            messages,
+            sections,
+            catch_interrupt,
+        } = self;
+
+        Box::pin(async move {
+            let mut interaction = Interaction::new(input.line_reader(), writer)
+                .with_cancel_token(CancelToken::new())
+                .with_messages(*messages)
+                .with_help_sections(sections);
+            if let Some(context) = context {
+                interaction = interaction.with_context(context.as_mut());
+            }
+
+            I::run_command(
+                &mut **scope,
+                command,
+                args,
+                &mut interaction,
+                *catch_interrupt,
+            )
+            .await
+        })
+    }
+}
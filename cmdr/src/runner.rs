@@ -1,81 +1,98 @@
+use crate::future::block_on;
 use crate::line_reader::LineReader;
+use crate::messages::Messages;
+use crate::middleware::Middleware;
+use crate::run_loop::{BlockingInput, RunLoop};
 use crate::scope::Scope;
-use crate::Line;
-use crate::{line_writer::LineWriter, result::Action, CommandResult};
-use std::fmt::Debug;
+use crate::{line_writer::LineWriter, CommandResult, Error};
+use std::any::Any;
+use std::sync::Arc;
 
 /// Wraps a LineReader and a Scope and allows using the scope to interpret commands from the
 /// LineReader
 #[derive(Debug)]
 pub struct Runner<R: LineReader, W: LineWriter> {
-    reader: R,
-    writer: W,
+    core: RunLoop<BlockingInput<R>, W>,
 }
 
 impl<R: LineReader, W: LineWriter> Runner<R, W> {
     /// Create a new runner that takes lines from the `reader` and executes them using the `scope`
     pub fn new(reader: R, writer: W) -> Self {
-        Runner { reader, writer }
+        Runner {
+            core: RunLoop::new(BlockingInput { reader }, writer),
+        }
     }
 
-    /// Start reading lines and executing them
-    pub fn run<S: Scope>(&mut self, scope: &mut S) -> CommandResult {
-        let mut result = self.run_scope(scope);
-
-        while let Ok(Action::NewScope(mut sub_scope)) = result {
-            result = self.run_scope(sub_scope.as_mut());
-        }
+    /// Set whether Ctrl-C at an empty prompt quits, when false a new prompt is shown instead.
+    /// Ctrl-C while a command runs always cancels the command
+    pub fn with_quit_on_ctrl_c(mut self, quit: bool) -> Self {
+        self.core.quit_on_ctrl_c = quit;
+        self
+    }
 
-        result
+    /// Set whether Ctrl-C in the terminal cancels running commands. Runners for remote users
+    /// should not be cancelled by Ctrl-C on the console
+    pub(crate) fn with_catch_interrupt(mut self, catch: bool) -> Self {
+        self.core.set_catch_interrupt(catch);
+        self
     }
 
-    /// Execute commands in this scope. Uses a LineReader to get commands and executes them one by
-    /// one until a command returns CommandResult::Quit
-    fn run_scope(&mut self, scope: &mut dyn Scope) -> CommandResult {
-        scope.before_loop();
+    /// Set whether records that commands write are written as JSON lines for scripts instead of
+    /// tables for humans, other output is not changed
+    pub fn with_json_output(mut self, json: bool) -> Self {
+        self.core.json_output = json;
+        self
+    }
 
-        let mut last_result = Ok(Action::Done);
-        let commands = scope.commands();
+    /// Show messages instead of the default English texts built into cmdr
+    pub fn with_messages<M: Messages + 'static>(self, messages: M) -> Self {
+        self.with_shared_messages(Arc::new(messages))
+    }
 
-        while let Ok(Action::Done) = last_result {
-            last_result = match self.reader.read_line(scope.prompt().as_ref()) {
-                Err(error) => Err(error),
-                Ok(line_string) => {
-                    let line = Line::try_parse(line_string.as_ref());
-                    match line {
-                        Err(error) => Err(error),
-                        Ok(line) => {
-                            let line = scope.before_command(line);
+    /// Show messages that are shared with other runners, like the runners of a server
+    pub(crate) fn with_shared_messages(mut self, messages: Arc<dyn Messages>) -> Self {
+        self.core.set_messages(messages);
+        self
+    }
 
-                            let result = match commands.command_for_line(&line) {
-                                Some(command) => {
-                                    scope.run_command(&command, &line.args, &mut self.writer)
-                                }
-                                None => scope.default(&line),
-                            };
+    /// Set the context that commands in all scopes can borrow with a `&mut Context<T>` argument
+    pub fn with_context<T: Any>(mut self, context: T) -> Self {
+        self.core.set_context(context);
+        self
+    }
 
-                            let result = if let Ok(Action::SubScope(mut sub_scope)) = result {
-                                self.run_scope(sub_scope.as_mut())
-                            } else {
-                                result
-                            };
+    /// Set a scope with commands that are available in every scope. Commands of the running scope
+    /// are used first, global commands are used when the running scope does not have the command
+    pub fn with_global_scope<S: Scope + 'static>(mut self, scope: S) -> Self {
+        self.core.set_global_scope(Box::new(scope));
+        self
+    }
 
-                            scope.after_command(&line, result)
-                        }
-                    }
-                }
-            };
+    /// Add middleware that runs around every command of the running scope and the global scope.
+    /// Middleware runs in the order it was added
+    pub fn with_middleware<M: Middleware + 'static>(mut self, middleware: M) -> Self {
+        self.core.middleware.push(Box::new(middleware));
+        self
+    }
 
-            if let Err(error) = last_result {
-                last_result = scope.handle_error_internal(error)
-            }
-        }
+    /// Start reading lines and executing them
+    pub fn run<S: Scope>(&mut self, scope: &mut S) -> CommandResult {
+        block_on(self.core.run(scope))
+    }
 
-        scope.after_loop();
+    /// Run like `run` from main() and return the exit code for the process. Returns 0 when the
+    /// loop finishes normally, fatal errors return their error code and other errors are printed
+    /// to stderr and return 1
+    pub fn run_main<S: Scope>(&mut self, scope: &mut S) -> i32 {
+        self.run_main_with(scope, Error::exit_code)
+    }
 
-        match last_result {
-            Ok(Action::Exit) => Ok(Action::Done),
-            _ => last_result,
-        }
+    /// Like `run_main` but `exit_code` chooses the exit code for errors
+    pub fn run_main_with<S, F>(&mut self, scope: &mut S, exit_code: F) -> i32
+    where
+        S: Scope,
+        F: FnOnce(&Error) -> i32,
+    {
+        block_on(self.core.run_main(scope, exit_code))
     }
 }
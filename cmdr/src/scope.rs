@@ -1,6 +1,11 @@
+#[cfg(feature = "async")]
+use crate::async_scope::AsyncScope;
 use crate::description::ScopeDescription;
-use crate::line_writer::LineWriter;
+use crate::interaction::Interaction;
+use crate::line_reader::NoLineReader;
+use crate::line_writer::{LineWriter, PrintlnWriter};
 use crate::result::{Action, CommandResult, Error};
+use crate::style::Style;
 use crate::{Line, ScopeCmdDescription};
 
 /// Trait for implementing a Scope object. This trait can be implemented directly but will most
@@ -10,26 +15,71 @@ pub trait Scope {
     /// the help function and maybe in the future by tab completion.
     fn commands(&self) -> ScopeDescription;
 
-    /// Run an entered command and return the result
+    /// Run an entered command and return the result. The interaction is used to write output
+    /// and to ask the user questions, before 0.4 this was a `&mut dyn LineWriter`
     fn run_command(
         &mut self,
         command: &ScopeCmdDescription,
         args: &[String],
-        writer: &mut dyn LineWriter,
+        interaction: &mut Interaction,
     ) -> CommandResult;
 
+    /// Return this scope as an AsyncScope when it has async commands. Implemented by the cmdr
+    /// macro, the default implementation returns None
+    #[cfg(feature = "async")]
+    fn as_async(&mut self) -> Option<&mut dyn AsyncScope> {
+        None
+    }
+
     /// Return the prompt for this scope. The default implementation returns > as the prompt but
     /// this can be overridden to return other strings or implement dynamically generated prompts
     fn prompt(&self) -> String {
         ">".to_string()
     }
 
-    /// Execute a help command
+    /// Descriptions of the sub scopes that commands in this scope can open, with the command that
+    /// opens them. Deeper sub scopes can be listed with the commands that lead to them, like
+    /// `user admin`. Used to generate reference documentation, the default implementation returns
+    /// none
+    fn sub_scopes(&self) -> Vec<(String, ScopeDescription)> {
+        Vec::new()
+    }
+
+    /// Execute a help command, prints the help to stdout. The help command of scopes generated
+    /// by the cmdr macro calls `help_with` unless this method is overridden
     fn help(&self, args: &[String]) -> CommandResult {
-        let command = args.get(0).map(|stuff| stuff.as_ref());
-        let help_text = self.commands().format_help_text(command)?;
+        self.help_with(
+            &mut Interaction::new(&mut NoLineReader, &mut PrintlnWriter {}),
+            args,
+        )
+    }
+
+    /// Execute a help command, writes the help to the interaction wrapped to fit its width
+    fn help_with(&self, interaction: &mut Interaction, args: &[String]) -> CommandResult {
+        let mut description = interaction
+            .help_sections()
+            .iter()
+            .fold(self.commands(), |description, (header, section)| {
+                description.with_section(header, section.clone())
+            });
+        let mut args = args;
+
+        // Help for commands of mounted scopes, like `help db query`
+        while args.len() > 1 {
+            match description.mount(&args[0]) {
+                Some(mount) => description = mount.clone(),
+                None => break,
+            }
+            args = &args[1..];
+        }
+
+        let command = args.first().map(|stuff| stuff.as_ref());
+
+        let help_text =
+            description.format_help(command, interaction.width(), interaction.messages())?;
 
-        println!("\n{}", help_text);
+        interaction.write_line("");
+        interaction.write_line(&help_text);
         Ok(Action::Done)
     }
 
@@ -41,23 +91,42 @@ pub trait Scope {
     }
 
     /// Error handling, first allow the user to handle the error, then handles or passes on
-    /// unhandled errors
+    /// unhandled errors. Errors are printed to stdout
     fn handle_error_internal(&mut self, error: Error) -> CommandResult {
+        self.handle_error_with(
+            error,
+            &mut Interaction::new(&mut NoLineReader, &mut PrintlnWriter {}),
+        )
+    }
+
+    /// Error handling like `handle_error_internal`, errors are written to the interaction in the
+    /// error style of its writer
+    fn handle_error_with(&mut self, error: Error, interaction: &mut Interaction) -> CommandResult {
         // Allow user to handle error in overridable handle_error
         match self.handle_error(error) {
             CommandResult::Err(error) => {
                 // Error was not handled by the user, handle it here
                 match error {
-                    Error::InvalidCommand(command) => {
-                        println!("Unknown command: {}", command);
+                    Error::InvalidCommand(_)
+                    | Error::InvalidNumberOfArguments(_)
+                    | Error::NoHelpForCommand(_)
+                    | Error::NoContext(_)
+                    | Error::AsyncCommand(_) => {
+                        interaction
+                            .write_styled_line(Style::Error, &interaction.messages().error(&error));
                         Ok(Action::Done)
                     }
-                    Error::InvalidNumberOfArguments(command) => {
-                        println!("Invalid number of arguments for command: {}", command);
+                    Error::Cancelled => {
+                        interaction.write_styled_line(
+                            Style::Warning,
+                            &interaction.messages().error(&error),
+                        );
                         Ok(Action::Done)
                     }
-                    Error::NoHelpForCommand(command) => {
-                        println!("No help available for command: {}", command);
+                    Error::Custom(_) => {
+                        for line in error.report(interaction.messages()).lines() {
+                            interaction.write_styled_line(Style::Error, line);
+                        }
                         Ok(Action::Done)
                     }
                     Error::EmptyLine => Ok(Action::Done),
@@ -91,3 +160,41 @@ pub trait Scope {
     /// Hook that is called after the command loop finishes, can be overridden
     fn after_loop(&mut self) {}
 }
+
+/// Run a command of a scope that is mounted as name, the first argument is the command in the
+/// mounted scope. Without arguments the help of the mounted scope is shown.
+///
+/// Only the command itself is run, the hooks and `handle_error` of the mounted scope are not
+/// called. Errors go to the scope that mounts it. Async commands of the mounted scope return
+/// `Error::AsyncCommand`, use `run_mounted_async` to run those.
+pub fn run_mounted(
+    scope: &mut dyn Scope,
+    name: &str,
+    args: &[String],
+    interaction: &mut Interaction,
+) -> CommandResult {
+    match args.split_first() {
+        Some((command, args)) => {
+            let commands = scope.commands();
+            let line = Line {
+                command: command.clone(),
+                args: args.to_vec(),
+            };
+
+            match commands.command_for_line(&line) {
+                Some(command) => scope.run_command(command, args, interaction),
+                None => Err(Error::InvalidCommand(format!("{} {}", name, command))),
+            }
+        }
+        None => {
+            let help_text =
+                scope
+                    .commands()
+                    .format_help(None, interaction.width(), interaction.messages())?;
+
+            interaction.write_line("");
+            interaction.write_line(&help_text);
+            Ok(Action::Done)
+        }
+    }
+}
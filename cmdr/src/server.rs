@@ -0,0 +1,534 @@
+//! A server that lets remote users run commands over TCP or Unix domain sockets. Every connection
+//! gets a fresh scope that runs on its own thread.
+//!
+//! ```no_run
+//! # use cmdr::*;
+//! # struct GreeterScope {}
+//! # #[cmdr] impl GreeterScope {}
+//! use cmdr::server::Server;
+//!
+//! let server = Server::new(|| GreeterScope {})
+//!     .with_max_connections(10)
+//!     .listen_tcp("127.0.0.1:9000")
+//!     .unwrap();
+//!
+//! // Stop accepting connections, close the open ones and wait for them to finish
+//! server.shutdown();
+//! ```
+
+use crate::line_reader::{LineReader, StreamLineReader};
+use crate::line_writer::{LineWriter, StreamLineWriter};
+use crate::messages::{DefaultMessages, Messages};
+use crate::protocol::{Frame, FramedLineReader, FramedLineWriter};
+use crate::runner::Runner;
+use crate::scope::Scope;
+use std::io::{self, ErrorKind, Read, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener, UnixStream};
+#[cfg(unix)]
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// How long the server waits between checks for new connections and shutdown
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Listens for connections and runs a new scope for every connection. Scopes are created by a
+/// factory function, they don't have to be Send because they are created on the connection thread.
+#[derive(Debug)]
+pub struct Server<F> {
+    factory: Arc<F>,
+    max_connections: usize,
+    framed: bool,
+    messages: Arc<dyn Messages>,
+}
+
+impl<S, F> Server<F>
+where
+    S: Scope,
+    F: Fn() -> S + Send + Sync + 'static,
+{
+    /// Construct a server that creates a scope for every connection by calling factory
+    pub fn new(factory: F) -> Self {
+        Server {
+            factory: Arc::new(factory),
+            max_connections: 16,
+            framed: false,
+            messages: Arc::new(DefaultMessages),
+        }
+    }
+
+    /// Set the maximum number of connections that are served at the same time, connections over
+    /// this limit are told to try again later and closed. The default is 16
+    pub fn with_max_connections(mut self, max_connections: usize) -> Self {
+        self.max_connections = max_connections;
+        self
+    }
+
+    /// Use the framed protocol that `cmdr-client` speaks instead of plain text. Plain text works
+    /// with tools like netcat, the framed protocol lets the client edit lines and hide passwords
+    pub fn with_framed_protocol(mut self, framed: bool) -> Self {
+        self.framed = framed;
+        self
+    }
+
+    /// Show messages instead of the default English texts to the users of the server
+    pub fn with_messages<M: Messages + 'static>(mut self, messages: M) -> Self {
+        self.messages = Arc::new(messages);
+        self
+    }
+
+    /// Start listening for TCP connections on a background thread
+    pub fn listen_tcp<A: ToSocketAddrs>(self, address: A) -> io::Result<ServerHandle> {
+        let listener = TcpListener::bind(address)?;
+        listener.set_nonblocking(true)?;
+        let local_addr = listener.local_addr()?;
+
+        let mut handle = self.start(move || listener.accept().map(|(stream, _)| stream), || ());
+        handle.local_addr = Some(local_addr);
+        Ok(handle)
+    }
+
+    /// Start listening for connections on a Unix domain socket on a background thread. The socket
+    /// file is removed when the server shuts down
+    #[cfg(unix)]
+    pub fn listen_unix<P: AsRef<Path>>(self, path: P) -> io::Result<ServerHandle> {
+        let path: PathBuf = path.as_ref().to_path_buf();
+        let listener = UnixListener::bind(&path)?;
+        listener.set_nonblocking(true)?;
+
+        Ok(self.start(
+            move || listener.accept().map(|(stream, _)| stream),
+            move || {
+                std::fs::remove_file(&path).ok();
+            },
+        ))
+    }
+
+    /// Run the accept loop on a new thread, cleanup is called after the loop ends
+    fn start<C, A, D>(self, accept: A, cleanup: D) -> ServerHandle
+    where
+        C: Connection,
+        A: FnMut() -> io::Result<C> + Send + 'static,
+        D: FnOnce() + Send + 'static,
+    {
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = stop.clone();
+
+        let thread = thread::spawn(move || {
+            self.accept_loop(accept, &thread_stop);
+            cleanup();
+        });
+
+        ServerHandle {
+            stop,
+            thread: Some(thread),
+            local_addr: None,
+        }
+    }
+
+    /// Accept connections until stop is set, then close all connections and wait for them
+    fn accept_loop<C, A>(self, mut accept: A, stop: &AtomicBool)
+    where
+        C: Connection,
+        A: FnMut() -> io::Result<C>,
+    {
+        let mut sessions: Vec<Session<C>> = Vec::new();
+
+        while !stop.load(Ordering::SeqCst) {
+            let (finished, running): (Vec<_>, Vec<_>) = sessions
+                .into_iter()
+                .partition(|session| session.done.load(Ordering::SeqCst));
+            sessions = running;
+            for session in finished {
+                session.thread.join().ok();
+            }
+
+            match accept() {
+                // Sessions can end while waiting for a connection
+                Ok(mut stream) if running_sessions(&sessions) >= self.max_connections => {
+                    let refusal = format!("{}\n", self.messages.too_many_connections());
+                    stream.write_all(refusal.as_bytes()).ok();
+                    stream.close();
+                }
+                Ok(stream) => {
+                    match Session::start(
+                        stream,
+                        self.factory.clone(),
+                        self.messages.clone(),
+                        self.framed,
+                    ) {
+                        Ok(session) => sessions.push(session),
+                        Err(_) => continue,
+                    }
+                }
+                Err(ref error) if error.kind() == ErrorKind::WouldBlock => {
+                    thread::sleep(POLL_INTERVAL)
+                }
+                // Errors like running out of file descriptors can go away, keep on listening
+                Err(_) => thread::sleep(POLL_INTERVAL),
+            }
+        }
+
+        // Closing the connections ends the scopes like Ctrl-D would
+        for session in &sessions {
+            session.stream.close();
+        }
+        for session in sessions {
+            session.thread.join().ok();
+        }
+    }
+}
+
+/// Handle to a running server, the server is shut down when the handle is dropped
+#[derive(Debug)]
+pub struct ServerHandle {
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+    local_addr: Option<SocketAddr>,
+}
+
+impl ServerHandle {
+    /// The address a TCP server is listening on, useful when listening on port 0
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.local_addr
+    }
+
+    /// Stop accepting connections, close open connections and wait until they are finished
+    pub fn shutdown(self) {}
+}
+
+impl Drop for ServerHandle {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+
+        if let Some(thread) = self.thread.take() {
+            thread.join().ok();
+        }
+    }
+}
+
+/// A connection that is being served on its own thread
+struct Session<C> {
+    stream: C,
+    done: Arc<AtomicBool>,
+    thread: JoinHandle<()>,
+}
+
+impl<C: Connection> Session<C> {
+    /// Run a new scope for the connection on a new thread
+    fn start<S, F>(
+        stream: C,
+        factory: Arc<F>,
+        messages: Arc<dyn Messages>,
+        framed: bool,
+    ) -> io::Result<Self>
+    where
+        S: Scope,
+        F: Fn() -> S + Send + Sync + 'static,
+    {
+        stream.set_blocking()?;
+        let reader = stream.try_clone()?;
+        let prompt = stream.try_clone()?;
+        let writer = stream.try_clone()?;
+        let closer = stream.try_clone()?;
+
+        let done = Arc::new(AtomicBool::new(false));
+        let thread_done = done.clone();
+
+        let thread = thread::spawn(move || {
+            let mut end = SessionEnd {
+                closer,
+                done: thread_done,
+            };
+
+            if framed {
+                run_session(
+                    FramedLineReader::new(reader, prompt),
+                    FramedLineWriter::new(writer),
+                    factory.as_ref(),
+                    messages,
+                );
+                Frame::End.write_to(&mut end.closer).ok();
+            } else {
+                run_session(
+                    StreamLineReader::new(reader, prompt),
+                    StreamLineWriter::new(writer),
+                    factory.as_ref(),
+                    messages,
+                );
+            }
+        });
+
+        Ok(Session {
+            stream,
+            done,
+            thread,
+        })
+    }
+}
+
+/// The number of sessions that did not end yet
+fn running_sessions<C>(sessions: &[Session<C>]) -> usize {
+    sessions
+        .iter()
+        .filter(|session| !session.done.load(Ordering::SeqCst))
+        .count()
+}
+
+/// Closes the connection and marks the session as done when the session thread ends, also when
+/// a command panics
+struct SessionEnd<C: Connection> {
+    closer: C,
+    done: Arc<AtomicBool>,
+}
+
+impl<C: Connection> Drop for SessionEnd<C> {
+    fn drop(&mut self) {
+        self.done.store(true, Ordering::SeqCst);
+        self.closer.close();
+    }
+}
+
+/// Run a fresh scope until the user quits or the connection is closed
+fn run_session<R, W, S, F>(reader: R, writer: W, factory: &F, messages: Arc<dyn Messages>)
+where
+    R: LineReader,
+    W: LineWriter,
+    S: Scope,
+    F: Fn() -> S,
+{
+    let mut runner = Runner::new(reader, writer)
+        .with_catch_interrupt(false)
+        .with_shared_messages(messages);
+    runner.run(&mut factory()).ok();
+}
+
+/// A socket stream that a scope can be served on
+trait Connection: Read + Write + Send + Sized + 'static {
+    fn try_clone(&self) -> io::Result<Self>;
+
+    /// Make sure reads block, accepted streams can inherit non-blocking mode from the listener
+    fn set_blocking(&self) -> io::Result<()>;
+
+    /// Close both directions of the stream, this also ends blocking reads on other threads
+    fn close(&self);
+}
+
+impl Connection for TcpStream {
+    fn try_clone(&self) -> io::Result<Self> {
+        TcpStream::try_clone(self)
+    }
+
+    fn set_blocking(&self) -> io::Result<()> {
+        self.set_nonblocking(false)
+    }
+
+    fn close(&self) {
+        self.shutdown(Shutdown::Both).ok();
+    }
+}
+
+#[cfg(unix)]
+impl Connection for UnixStream {
+    fn try_clone(&self) -> io::Result<Self> {
+        UnixStream::try_clone(self)
+    }
+
+    fn set_blocking(&self) -> io::Result<()> {
+        self.set_nonblocking(false)
+    }
+
+    fn close(&self) {
+        self.shutdown(Shutdown::Both).ok();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::description::{ScopeCmdDescription, ScopeDescription};
+    use crate::interaction::Interaction;
+    use crate::line_writer::LineWriter;
+    use crate::result::{Action, CommandResult, Error};
+    use std::io::{BufRead, BufReader};
+
+    struct EchoScope {}
+
+    impl Scope for EchoScope {
+        fn commands(&self) -> ScopeDescription {
+            ScopeDescription::new(
+                None,
+                vec![
+                    ScopeCmdDescription::new("echo".to_string(), vec![], None),
+                    ScopeCmdDescription::new("panic".to_string(), vec![], None),
+                ],
+            )
+        }
+
+        fn run_command(
+            &mut self,
+            command: &ScopeCmdDescription,
+            args: &[String],
+            interaction: &mut Interaction,
+        ) -> CommandResult {
+            match command.name() {
+                "echo" => {
+                    interaction.write_line(&args.join(" "));
+                    Ok(Action::Done)
+                }
+                "panic" => panic!("command panicked"),
+                _ => Err(Error::InvalidCommand(command.name().to_string())),
+            }
+        }
+    }
+
+    fn connect(server: &ServerHandle) -> (TcpStream, BufReader<TcpStream>) {
+        let stream = TcpStream::connect(server.local_addr().unwrap()).unwrap();
+        let reader = BufReader::new(stream.try_clone().unwrap());
+        (stream, reader)
+    }
+
+    fn read_line(reader: &mut BufReader<TcpStream>) -> String {
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        line
+    }
+
+    #[test]
+    fn server_should_run_commands_over_tcp() {
+        let server = Server::new(|| EchoScope {})
+            .listen_tcp("127.0.0.1:0")
+            .unwrap();
+        let (mut stream, mut reader) = connect(&server);
+
+        stream.write_all(b"echo hello world\n").unwrap();
+
+        assert_eq!(read_line(&mut reader), "> hello world\n");
+        server.shutdown();
+    }
+
+    #[test]
+    fn server_should_refuse_connections_over_limit() {
+        let server = Server::new(|| EchoScope {})
+            .with_max_connections(1)
+            .listen_tcp("127.0.0.1:0")
+            .unwrap();
+        let (mut first, mut first_reader) = connect(&server);
+        first.write_all(b"echo first\n").unwrap();
+        assert_eq!(read_line(&mut first_reader), "> first\n");
+
+        let (_, mut second_reader) = connect(&server);
+
+        assert_eq!(
+            read_line(&mut second_reader),
+            "Too many connections, try again later\n"
+        );
+    }
+
+    #[derive(Debug)]
+    struct DutchMessages;
+
+    impl Messages for DutchMessages {
+        fn too_many_connections(&self) -> String {
+            "Te veel verbindingen".to_string()
+        }
+    }
+
+    #[test]
+    fn server_should_refuse_connections_with_messages() {
+        let server = Server::new(|| EchoScope {})
+            .with_max_connections(0)
+            .with_messages(DutchMessages)
+            .listen_tcp("127.0.0.1:0")
+            .unwrap();
+        let (_, mut reader) = connect(&server);
+
+        assert_eq!(read_line(&mut reader), "Te veel verbindingen\n");
+    }
+
+    #[test]
+    fn shutdown_should_close_open_connections() {
+        let server = Server::new(|| EchoScope {})
+            .listen_tcp("127.0.0.1:0")
+            .unwrap();
+        let (mut stream, mut reader) = connect(&server);
+        stream.write_all(b"echo ready\n").unwrap();
+        read_line(&mut reader);
+
+        server.shutdown();
+
+        // Only the next prompt is left before the connection is closed
+        let mut rest = String::new();
+        reader.read_to_string(&mut rest).unwrap();
+        assert_eq!(rest, "> ");
+    }
+
+    #[test]
+    fn panicking_command_should_close_its_connection_only() {
+        let server = Server::new(|| EchoScope {})
+            .with_max_connections(1)
+            .listen_tcp("127.0.0.1:0")
+            .unwrap();
+        let (mut stream, mut reader) = connect(&server);
+
+        stream.write_all(b"panic\n").unwrap();
+        let mut rest = String::new();
+        reader.read_to_string(&mut rest).unwrap();
+        assert_eq!(rest, "> ");
+
+        // The session no longer counts as a connection
+        let (mut stream, mut reader) = connect(&server);
+        stream.write_all(b"echo still serving\n").unwrap();
+        assert_eq!(read_line(&mut reader), "> still serving\n");
+    }
+
+    #[test]
+    fn framed_server_should_send_frames() {
+        let server = Server::new(|| EchoScope {})
+            .with_framed_protocol(true)
+            .listen_tcp("127.0.0.1:0")
+            .unwrap();
+        let (mut stream, mut reader) = connect(&server);
+
+        assert_eq!(
+            Frame::read_from(&mut reader).unwrap(),
+            Some(Frame::Prompt(">".to_string()))
+        );
+        Frame::Line("echo framed".to_string())
+            .write_to(&mut stream)
+            .unwrap();
+        assert_eq!(
+            Frame::read_from(&mut reader).unwrap(),
+            Some(Frame::Output("framed\n".to_string()))
+        );
+        assert_eq!(
+            Frame::read_from(&mut reader).unwrap(),
+            Some(Frame::Prompt(">".to_string()))
+        );
+
+        Frame::End.write_to(&mut stream).unwrap();
+        assert_eq!(Frame::read_from(&mut reader).unwrap(), Some(Frame::End));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn server_should_run_commands_over_unix_socket() {
+        let path = std::env::temp_dir().join(format!("cmdr-test-{}.sock", std::process::id()));
+        let server = Server::new(|| EchoScope {}).listen_unix(&path).unwrap();
+
+        let mut stream = UnixStream::connect(&path).unwrap();
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        stream.write_all(b"echo unix\n").unwrap();
+
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        assert_eq!(line, "> unix\n");
+
+        server.shutdown();
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,54 @@
+//! Output styles that can be used to highlight output
+
+/// Style of a piece of output. Writers that write to a terminal render styles using ANSI color
+/// codes, other writers write plain text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Style {
+    /// Errors, rendered in red
+    Error,
+
+    /// Warnings, rendered in yellow
+    Warning,
+
+    /// Successful results, rendered in green
+    Success,
+
+    /// Emphasized text, rendered in bold
+    Emphasis,
+
+    /// Less important text, rendered dimmed
+    Dim,
+}
+
+impl Style {
+    /// The ANSI escape code that starts this style
+    fn code(self) -> &'static str {
+        match self {
+            Style::Error => "\x1b[31m",
+            Style::Warning => "\x1b[33m",
+            Style::Success => "\x1b[32m",
+            Style::Emphasis => "\x1b[1m",
+            Style::Dim => "\x1b[2m",
+        }
+    }
+
+    /// Wrap text in the ANSI escape codes for this style
+    pub fn paint(self, text: &str) -> String {
+        format!("{}{}\x1b[0m", self.code(), text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn style_should_paint_text_with_ansi_codes() {
+        assert_eq!(Style::Error.paint("error"), "\x1b[31merror\x1b[0m");
+    }
+
+    #[test]
+    fn style_should_reset_after_text() {
+        assert!(Style::Dim.paint("dim").ends_with("\x1b[0m"));
+    }
+}
@@ -0,0 +1,508 @@
+//! Helpers to find out about and interact with the terminal cmdr is running in
+#[cfg(unix)]
+use std::cell::UnsafeCell;
+use std::env;
+use std::ffi::OsStr;
+#[cfg(unix)]
+use std::os::raw::c_int;
+#[cfg(unix)]
+use std::ptr;
+#[cfg(unix)]
+use std::sync::atomic::AtomicPtr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+#[cfg(unix)]
+use std::sync::{Mutex, MutexGuard, Once, PoisonError};
+use terminal_size::{terminal_size, Height, Width};
+
+/// Check if stdout is attached to a terminal
+#[cfg(unix)]
+pub(crate) fn is_tty() -> bool {
+    unsafe { libc::isatty(libc::STDOUT_FILENO) == 1 }
+}
+
+/// Check if stdout is attached to a terminal, only consoles have a size on this platform
+#[cfg(not(unix))]
+pub(crate) fn is_tty() -> bool {
+    terminal_size().is_some()
+}
+
+/// Check if both stdin and stdout are attached to a terminal so we can interact with the user
+/// directly
+#[cfg(unix)]
+pub(crate) fn is_interactive() -> bool {
+    let stdin = unsafe { libc::isatty(libc::STDIN_FILENO) == 1 };
+    stdin && is_tty()
+}
+
+/// Check if stdout is attached to a terminal, stdin is assumed to be attached to the same console
+/// on this platform
+#[cfg(not(unix))]
+pub(crate) fn is_interactive() -> bool {
+    is_tty()
+}
+
+/// Check if colored output should be used for stdout. Colors are only used when writing to a
+/// terminal and can be turned off by setting the NO_COLOR environment variable
+pub(crate) fn use_color() -> bool {
+    color_enabled(is_tty(), env::var_os("NO_COLOR").as_deref())
+}
+
+/// Decide if colors should be used from whether output goes to a terminal and the value of the
+/// NO_COLOR environment variable, an empty NO_COLOR is ignored
+fn color_enabled(tty: bool, no_color: Option<&OsStr>) -> bool {
+    tty && no_color.map_or(true, OsStr::is_empty)
+}
+
+/// The width of the terminal in characters if stdout is attached to a terminal
+pub(crate) fn width() -> Option<usize> {
+    terminal_size().map(|(Width(width), _)| width as usize)
+}
+
+/// The height of the terminal in lines if stdout is attached to a terminal
+pub(crate) fn height() -> Option<usize> {
+    terminal_size().map(|(_, Height(height))| height as usize)
+}
+
+/// Read a single key press from stdin without waiting for the user to press enter. Ctrl-C is
+/// read as the key '\x03' instead of stopping the process, keys that send an escape sequence like
+/// the arrow keys are read as '\x1b'
+#[cfg(unix)]
+pub(crate) fn read_key() -> Option<char> {
+    let _mode = InputMode::without(libc::ICANON | libc::ECHO | libc::ISIG)?;
+    let mut key = [0u8; 8];
+
+    loop {
+        // Read directly from the file descriptor, the buffered stdin in std would swallow input
+        // that is meant for the line reader. A whole escape sequence or UTF-8 character arrives
+        // in a single read
+        let read = unsafe {
+            libc::read(
+                libc::STDIN_FILENO,
+                key.as_mut_ptr() as *mut libc::c_void,
+                key.len(),
+            )
+        };
+
+        if read > 0 {
+            return String::from_utf8_lossy(&key[..read as usize])
+                .chars()
+                .next();
+        }
+        if read == 0 || std::io::Error::last_os_error().kind() != std::io::ErrorKind::Interrupted {
+            return None;
+        }
+    }
+}
+
+/// Read a single key press from stdin, on this platform the user has to press enter
+#[cfg(not(unix))]
+pub(crate) fn read_key() -> Option<char> {
+    let mut line = String::new();
+    match std::io::stdin().read_line(&mut line) {
+        Ok(0) | Err(_) => None,
+        Ok(_) => Some(line.chars().next().unwrap_or('\n')),
+    }
+}
+
+/// Read a line from stdin in the normal line-buffered mode
+pub(crate) fn read_line() -> Option<String> {
+    let mut line = String::new();
+    match std::io::stdin().read_line(&mut line) {
+        Ok(0) | Err(_) => None,
+        Ok(_) => Some(line.trim_end_matches(&['\r', '\n'][..]).to_string()),
+    }
+}
+
+/// Read a line from stdin without showing what is typed. Ctrl-C returns an error of kind
+/// Interrupted, Ctrl-D on an empty line or the end of input an error of kind UnexpectedEof
+#[cfg(unix)]
+pub(crate) fn read_password() -> std::io::Result<String> {
+    let mode = match InputMode::without(libc::ICANON | libc::ECHO | libc::ISIG) {
+        Some(mode) => mode,
+        None => {
+            return read_line()
+                .ok_or_else(|| std::io::Error::from(std::io::ErrorKind::UnexpectedEof))
+        }
+    };
+    let password = read_password_keys(&mut read_byte);
+
+    // The enter key was not echoed either
+    drop(mode);
+    println!();
+    password
+}
+
+/// Read a line from stdin, on this platform what is typed is shown
+#[cfg(not(unix))]
+pub(crate) fn read_password() -> std::io::Result<String> {
+    read_line().ok_or_else(|| std::io::Error::from(std::io::ErrorKind::UnexpectedEof))
+}
+
+/// Read a single byte from stdin, None at the end of input
+#[cfg(unix)]
+fn read_byte() -> std::io::Result<Option<u8>> {
+    let mut byte = [0u8; 1];
+
+    loop {
+        let read = unsafe {
+            libc::read(
+                libc::STDIN_FILENO,
+                byte.as_mut_ptr() as *mut libc::c_void,
+                1,
+            )
+        };
+
+        match read {
+            1 => return Ok(Some(byte[0])),
+            0 => return Ok(None),
+            _ => {
+                let error = std::io::Error::last_os_error();
+                if error.kind() != std::io::ErrorKind::Interrupted {
+                    return Err(error);
+                }
+            }
+        }
+    }
+}
+
+/// Collect the keys of a password that is typed without line editing by the terminal, handles
+/// enter, backspace, Ctrl-C and Ctrl-D
+#[cfg(unix)]
+fn read_password_keys(
+    next: &mut dyn FnMut() -> std::io::Result<Option<u8>>,
+) -> std::io::Result<String> {
+    let mut password = Vec::new();
+
+    loop {
+        match next()? {
+            Some(b'\r') | Some(b'\n') => break,
+            Some(0x03) => return Err(std::io::ErrorKind::Interrupted.into()),
+            Some(0x04) if password.is_empty() => {
+                return Err(std::io::ErrorKind::UnexpectedEof.into())
+            }
+            None => return Err(std::io::ErrorKind::UnexpectedEof.into()),
+            Some(0x7f) | Some(0x08) => {
+                // Remove the whole last character, continuation bytes of UTF-8 start with 10
+                while let Some(byte) = password.pop() {
+                    if byte & 0xc0 != 0x80 {
+                        break;
+                    }
+                }
+            }
+            Some(byte) => password.push(byte),
+        }
+    }
+
+    Ok(String::from_utf8_lossy(&password).into_owned())
+}
+
+/// Changes the local mode flags of the terminal attached to stdin, restores the original flags
+/// when dropped
+#[cfg(unix)]
+struct InputMode {
+    original: libc::termios,
+}
+
+#[cfg(unix)]
+impl InputMode {
+    /// Turn off flags until the returned value is dropped, returns None when stdin is not a
+    /// terminal
+    fn without(flags: libc::tcflag_t) -> Option<Self> {
+        unsafe {
+            let mut original: libc::termios = std::mem::zeroed();
+            if libc::tcgetattr(libc::STDIN_FILENO, &mut original) != 0 {
+                return None;
+            }
+
+            let mut changed = original;
+            changed.c_lflag &= !flags;
+            changed.c_cc[libc::VMIN] = 1;
+            changed.c_cc[libc::VTIME] = 0;
+
+            if libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &changed) != 0 {
+                return None;
+            }
+
+            Some(InputMode { original })
+        }
+    }
+}
+
+#[cfg(unix)]
+impl Drop for InputMode {
+    fn drop(&mut self) {
+        unsafe {
+            libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &self.original);
+        }
+    }
+}
+
+/// Counts Ctrl-C presses and finds out which CatchInterrupt saw them
+#[derive(Debug)]
+struct Interrupts {
+    count: AtomicUsize,
+    latest_catch: AtomicUsize,
+}
+
+impl Interrupts {
+    const fn new() -> Self {
+        Interrupts {
+            count: AtomicUsize::new(0),
+            latest_catch: AtomicUsize::new(0),
+        }
+    }
+
+    /// Start catching, returns the count to compare with later
+    fn catch(&self) -> usize {
+        let start = self.count.load(Ordering::SeqCst);
+        self.latest_catch.store(start, Ordering::SeqCst);
+        start
+    }
+
+    /// Check if Ctrl-C was pressed since catching started at start
+    fn since(&self, start: usize) -> bool {
+        self.count.load(Ordering::SeqCst) != start
+    }
+
+    /// Count a Ctrl-C press, returns true when it was pressed before since the latest catch started
+    fn interrupt(&self) -> bool {
+        self.count.fetch_add(1, Ordering::SeqCst) != self.latest_catch.load(Ordering::SeqCst)
+    }
+}
+
+/// Ctrl-C presses of the whole process
+static INTERRUPTS: Interrupts = Interrupts::new();
+
+/// Catches Ctrl-C instead of stopping the process until dropped. Pressing Ctrl-C a second time
+/// stops the process anyway, so commands that never check for cancellation can still be stopped.
+/// Several CatchInterrupt values can exist at the same time, also on different threads, the
+/// original handler is restored when the last one is dropped
+#[derive(Debug)]
+pub(crate) struct CatchInterrupt {
+    start: usize,
+}
+
+impl CatchInterrupt {
+    /// Start catching Ctrl-C
+    pub(crate) fn new() -> Self {
+        #[cfg(unix)]
+        {
+            let mut installation = installation();
+            if installation.count == 0 {
+                installation.install();
+            }
+            installation.count += 1;
+        }
+
+        CatchInterrupt {
+            start: INTERRUPTS.catch(),
+        }
+    }
+
+    /// The number of Ctrl-C presses before this was created, for `interrupted_since`
+    pub(crate) fn start(&self) -> usize {
+        self.start
+    }
+
+    /// Check if Ctrl-C was pressed since this was created
+    pub(crate) fn interrupted(&self) -> bool {
+        interrupted_since(self.start)
+    }
+}
+
+/// Check if Ctrl-C was pressed since a CatchInterrupt with start was created
+pub(crate) fn interrupted_since(start: usize) -> bool {
+    INTERRUPTS.since(start)
+}
+
+#[cfg(unix)]
+impl Drop for CatchInterrupt {
+    fn drop(&mut self) {
+        let mut installation = installation();
+        installation.count -= 1;
+        if installation.count == 0 {
+            installation.restore();
+        }
+    }
+}
+
+/// How many CatchInterrupt values exist and the signal action that was replaced for them
+#[cfg(unix)]
+struct Installation {
+    count: usize,
+    /// The whole action is restored, handlers like the one of tokio depend on their flags
+    original: libc::sigaction,
+}
+
+#[cfg(unix)]
+impl Installation {
+    fn install(&mut self) {
+        // The handler is not installed yet so it can not read the mode while it is written
+        unsafe {
+            let mode = &mut *SAVED_MODE.0.get();
+            let mut current: libc::termios = std::mem::zeroed();
+            *mode = if libc::tcgetattr(libc::STDIN_FILENO, &mut current) == 0 {
+                Some(current)
+            } else {
+                None
+            };
+
+            let handler: extern "C" fn(c_int) = on_interrupt;
+            let mut action: libc::sigaction = std::mem::zeroed();
+            action.sa_sigaction = handler as libc::sighandler_t;
+            action.sa_flags = libc::SA_RESTART;
+            libc::sigemptyset(&mut action.sa_mask);
+            libc::sigaction(libc::SIGINT, &action, &mut self.original);
+        }
+    }
+
+    fn restore(&mut self) {
+        unsafe {
+            libc::sigaction(libc::SIGINT, &self.original, ptr::null_mut());
+        }
+    }
+}
+
+/// Lock the installation, the mutex is created the first time it is needed
+#[cfg(unix)]
+fn installation() -> MutexGuard<'static, Installation> {
+    static CREATE: Once = Once::new();
+    static INSTALLATION: AtomicPtr<Mutex<Installation>> = AtomicPtr::new(ptr::null_mut());
+
+    CREATE.call_once(|| {
+        let installation = Box::new(Mutex::new(Installation {
+            count: 0,
+            original: unsafe { std::mem::zeroed() },
+        }));
+        INSTALLATION.store(Box::into_raw(installation), Ordering::SeqCst);
+    });
+
+    // The mutex is never freed
+    let installation = unsafe { &*INSTALLATION.load(Ordering::SeqCst) };
+    installation.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// The terminal mode from when catching started, restored before the process is stopped
+#[cfg(unix)]
+struct SavedMode(UnsafeCell<Option<libc::termios>>);
+
+// Only written while the signal handler that reads it is not installed
+#[cfg(unix)]
+unsafe impl Sync for SavedMode {}
+
+#[cfg(unix)]
+static SAVED_MODE: SavedMode = SavedMode(UnsafeCell::new(None));
+
+#[cfg(unix)]
+extern "C" fn on_interrupt(_signal: c_int) {
+    if INTERRUPTS.interrupt() {
+        // Second Ctrl-C, the command did not stop so stop the process like the default handler.
+        // A command may have turned off echo or line editing, the user gets their terminal back
+        unsafe {
+            if let Some(mode) = &*SAVED_MODE.0.get() {
+                libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, mode);
+            }
+            libc::_exit(130)
+        }
+    }
+}
+
+/// Check if the signal handler that catches Ctrl-C is installed
+#[cfg(all(test, unix))]
+fn catching() -> bool {
+    let handler: extern "C" fn(c_int) = on_interrupt;
+    let mut current: libc::sigaction = unsafe { std::mem::zeroed() };
+    // A null action only queries the current one
+    unsafe { libc::sigaction(libc::SIGINT, ptr::null(), &mut current) };
+    current.sa_sigaction == handler as libc::sighandler_t
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_use_color_on_terminal() {
+        assert!(color_enabled(true, None));
+    }
+
+    #[test]
+    fn should_not_use_color_when_not_on_terminal() {
+        assert!(!color_enabled(false, None));
+    }
+
+    #[test]
+    fn should_not_use_color_when_no_color_is_set() {
+        assert!(!color_enabled(true, Some(OsStr::new("1"))));
+    }
+
+    #[test]
+    fn should_ignore_empty_no_color() {
+        assert!(color_enabled(true, Some(OsStr::new(""))));
+        assert!(!color_enabled(false, Some(OsStr::new(""))));
+    }
+
+    /// Type keys into read_password_keys
+    #[cfg(unix)]
+    fn type_password(keys: &[u8]) -> std::io::Result<String> {
+        let mut keys = keys.iter().copied();
+        read_password_keys(&mut || Ok(keys.next()))
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn password_should_handle_editing_keys() {
+        assert_eq!(type_password(b"secret\n").unwrap(), "secret");
+        assert_eq!(
+            type_password("sé\x7f\x7fx\r".as_bytes()).unwrap(),
+            "x".to_string()
+        );
+        assert_eq!(
+            type_password(b"sec\x03").unwrap_err().kind(),
+            std::io::ErrorKind::Interrupted
+        );
+        assert_eq!(
+            type_password(b"\x04").unwrap_err().kind(),
+            std::io::ErrorKind::UnexpectedEof
+        );
+        assert_eq!(
+            type_password(b"sec").unwrap_err().kind(),
+            std::io::ErrorKind::UnexpectedEof
+        );
+    }
+
+    #[test]
+    fn second_interrupt_should_be_noticed() {
+        let interrupts = Interrupts::new();
+        let start = interrupts.catch();
+
+        assert!(!interrupts.since(start));
+        assert!(!interrupts.interrupt());
+        assert!(interrupts.since(start));
+        assert!(interrupts.interrupt());
+    }
+
+    #[test]
+    fn new_catch_should_not_hide_earlier_interrupts() {
+        let interrupts = Interrupts::new();
+        let first = interrupts.catch();
+        interrupts.interrupt();
+        let second = interrupts.catch();
+
+        assert!(interrupts.since(first));
+        assert!(!interrupts.since(second));
+        assert!(!interrupts.interrupt());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn handler_should_stay_installed_until_last_catch_is_dropped() {
+        let first = CatchInterrupt::new();
+        let second = CatchInterrupt::new();
+
+        // Dropped in the same order as they were created, like catches on different threads can be
+        drop(first);
+        assert!(catching());
+        drop(second);
+    }
+}
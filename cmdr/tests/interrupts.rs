@@ -0,0 +1,117 @@
+//! Presses Ctrl-C while runners run commands. Ctrl-C reaches the whole process, so these tests
+//! run in a test binary of their own
+#![cfg(unix)]
+use cmdr::line_reader::FileLineReader;
+use cmdr::line_writer::StreamLineWriter;
+use cmdr::*;
+use std::io::sink;
+use std::os::raw::c_int;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+
+/// The tests change how SIGINT is handled for the whole process, they run one at a time while
+/// they hold a Serial
+struct Serial;
+
+static RUNNING: AtomicBool = AtomicBool::new(false);
+
+fn serial() -> Serial {
+    while RUNNING
+        .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+        .is_err()
+    {
+        thread::yield_now();
+    }
+    Serial
+}
+
+impl Drop for Serial {
+    fn drop(&mut self) {
+        RUNNING.store(false, Ordering::SeqCst);
+    }
+}
+
+struct InnerScope {}
+
+#[cmdr]
+impl InnerScope {
+    /// Do nothing
+    #[cmd]
+    fn noop(&mut self, _args: &[String]) -> CommandResult {
+        Ok(Action::Done)
+    }
+}
+
+struct OuterScope {}
+
+#[cmdr]
+impl OuterScope {
+    /// Press Ctrl-C, then run another runner before checking for cancellation
+    #[cmd]
+    fn nested(&mut self, interaction: &mut Interaction, _args: &[String]) -> CommandResult {
+        unsafe { libc::raise(libc::SIGINT) };
+
+        Runner::new(
+            FileLineReader::new("noop\n".as_bytes()),
+            StreamLineWriter::new(sink()),
+        )
+        .run(&mut InnerScope {})?;
+
+        let cancelled = interaction.cancel_token().is_cancelled();
+        interaction.write_line(&format!("cancelled: {}", cancelled));
+        Ok(Action::Done)
+    }
+}
+
+#[test]
+fn nested_runner_should_not_hide_interrupt_of_running_command() {
+    let _serial = serial();
+    let mut output = Vec::new();
+    let result = Runner::new(
+        FileLineReader::new("nested\n".as_bytes()),
+        StreamLineWriter::new(&mut output),
+    )
+    .run(&mut OuterScope {});
+
+    assert!(result.is_ok());
+    assert_eq!(String::from_utf8(output).unwrap(), "cancelled: true\n");
+}
+
+extern "C" fn ignore_interrupt(_signal: c_int, _info: *mut libc::siginfo_t, _: *mut libc::c_void) {}
+
+/// The SIGINT action that is installed now
+fn current_action() -> libc::sigaction {
+    let mut action: libc::sigaction = unsafe { std::mem::zeroed() };
+    unsafe { libc::sigaction(libc::SIGINT, std::ptr::null(), &mut action) };
+    action
+}
+
+#[test]
+fn runner_should_restore_signal_action_with_its_flags() {
+    let _serial = serial();
+    let handler: extern "C" fn(c_int, *mut libc::siginfo_t, *mut libc::c_void) = ignore_interrupt;
+    let original = unsafe {
+        let mut action: libc::sigaction = std::mem::zeroed();
+        action.sa_sigaction = handler as libc::sighandler_t;
+        action.sa_flags = libc::SA_SIGINFO | libc::SA_ONSTACK;
+        libc::sigemptyset(&mut action.sa_mask);
+        let mut previous: libc::sigaction = std::mem::zeroed();
+        libc::sigaction(libc::SIGINT, &action, &mut previous);
+        previous
+    };
+
+    let result = Runner::new(
+        FileLineReader::new("noop\n".as_bytes()),
+        StreamLineWriter::new(sink()),
+    )
+    .run(&mut InnerScope {});
+    let restored = current_action();
+    unsafe { libc::sigaction(libc::SIGINT, &original, std::ptr::null_mut()) };
+
+    assert!(result.is_ok());
+    assert_eq!(restored.sa_sigaction, handler as libc::sighandler_t);
+    assert_eq!(
+        restored.sa_flags & (libc::SA_SIGINFO | libc::SA_ONSTACK),
+        libc::SA_SIGINFO | libc::SA_ONSTACK
+    );
+}
@@ -0,0 +1,449 @@
+//! Runs scopes generated by the cmdr macro with scripted input
+use cmdr::line_reader::FileLineReader;
+use cmdr::*;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// Writer that keeps the output so it can be checked after the runner is done
+#[derive(Debug, Clone, Default)]
+struct Output(Rc<RefCell<String>>);
+
+impl LineWriter for Output {
+    fn write(&mut self, line: &str) {
+        self.0.borrow_mut().push_str(line)
+    }
+}
+
+type TestRunner = Runner<FileLineReader<&'static [u8]>, Output>;
+
+/// Run scope with the runner returned by setup, returns the result and the output
+fn run_with<S, F>(scope: &mut S, input: &'static str, setup: F) -> (CommandResult, String)
+where
+    S: Scope,
+    F: FnOnce(TestRunner) -> TestRunner,
+{
+    let output = Output::default();
+    let result = setup(Runner::new(
+        FileLineReader::new(input.as_bytes()),
+        output.clone(),
+    ))
+    .run(scope);
+
+    let text = output.0.borrow().clone();
+    (result, text)
+}
+
+fn run<S: Scope>(scope: &mut S, input: &'static str) -> (CommandResult, String) {
+    run_with(scope, input, |runner| runner)
+}
+
+struct GreeterScope {}
+
+#[cmdr]
+impl GreeterScope {
+    /// Greet someone
+    #[cmd]
+    fn greet(&mut self, writer: &mut dyn LineWriter, args: &[String]) -> CommandResult {
+        writer.write_line(&format!("Hello {}", args.join(" ")));
+        Ok(Action::Done)
+    }
+}
+
+struct OldHelpScope {}
+
+#[cmdr]
+impl OldHelpScope {
+    /// Overrides help with the signature from before help_with existed
+    fn help(&self, _args: &[String]) -> CommandResult {
+        Ok(Action::Quit)
+    }
+}
+
+#[test]
+fn help_should_list_commands() {
+    let (_, output) = run(&mut GreeterScope {}, "help\n");
+
+    assert_eq!(
+        output,
+        "\nThese are the valid commands in this scope:\n\nhelp\ngreet  Greet someone\n"
+    );
+}
+
+struct BuildScope {}
+
+#[cmdr]
+impl BuildScope {
+    /// Build in the background
+    #[cmd]
+    fn build(&mut self, _args: &[String]) -> CommandResult {
+        Ok(Action::Background(Job::new("build", |_| {
+            Ok("built".to_string())
+        })))
+    }
+}
+
+#[test]
+fn job_commands_should_be_available_after_first_job() {
+    let (_, output) = run(
+        &mut BuildScope {},
+        "jobs
+build
+wait
+help
+",
+    );
+
+    assert_eq!(
+        output,
+        "Unknown command: jobs\n[1] build\n[1] Done    build\nbuilt\n\
+        \nThese are the valid commands in this scope:\n\nhelp\nbuild  Build in the background\n\n\
+        These commands manage background jobs:\n\n\
+        jobs  List the background jobs that are running\n\
+        wait  Wait for a background job, or all jobs, to finish\n\
+        kill  Ask a background job to stop\n"
+    );
+}
+
+#[test]
+fn help_command_should_call_overridden_help() {
+    let (result, output) = run(&mut OldHelpScope {}, "help\ngreet\n");
+
+    assert!(matches!(result, Ok(Action::Quit)));
+    assert_eq!(output, "");
+}
+
+#[test]
+fn errors_should_be_written_to_runner_writer() {
+    let (result, output) = run(&mut GreeterScope {}, "shout\n");
+
+    assert!(result.is_ok());
+    assert_eq!(output, "Unknown command: shout\n");
+}
+
+#[derive(Debug)]
+struct DutchMessages;
+
+impl Messages for DutchMessages {
+    fn error(&self, error: &Error) -> String {
+        match error {
+            Error::InvalidCommand(command) => format!("Onbekend commando: {}", command),
+            error => error.to_string(),
+        }
+    }
+
+    fn help_header(&self) -> String {
+        "Dit zijn de geldige commando's:".to_string()
+    }
+}
+
+#[test]
+fn runner_should_pass_messages_to_scopes() {
+    let (_, output) = run_with(&mut GreeterScope {}, "shout\nhelp\n", |runner| {
+        runner.with_messages(DutchMessages)
+    });
+
+    assert!(output.starts_with(
+        "Onbekend commando: shout\n\nDit zijn de geldige commando's:\n\nhelp\ngreet  Greet someone\n"
+    ));
+}
+
+struct GlobalCommands {}
+
+#[cmdr(no_help)]
+impl GlobalCommands {
+    /// Show the version
+    #[cmd]
+    fn version(&mut self, writer: &mut dyn LineWriter, _args: &[String]) -> CommandResult {
+        writer.write_line("1.0");
+        Ok(Action::Done)
+    }
+}
+
+#[test]
+fn help_should_list_global_commands_in_separate_section() {
+    let (_, output) = run_with(&mut GreeterScope {}, "help\nhelp version\n", |runner| {
+        runner.with_global_scope(GlobalCommands {})
+    });
+
+    assert_eq!(
+        output,
+        "\nThese are the valid commands in this scope:\n\nhelp\ngreet  Greet someone\n\n\
+        These commands are available in every scope:\n\nversion  Show the version\n\
+        \nShow the version\n"
+    );
+}
+
+struct CounterScope {}
+
+#[cmdr]
+impl CounterScope {
+    /// Count and show the count
+    #[cmd]
+    fn count(
+        &mut self,
+        counter: &mut cmdr::Context<u32>,
+        writer: &mut dyn LineWriter,
+        _args: &[String],
+    ) -> CommandResult {
+        **counter += 1;
+        writer.write_line(&counter.to_string());
+        Ok(Action::Done)
+    }
+}
+
+#[test]
+fn commands_should_borrow_context_of_runner() {
+    let (result, output) = run_with(&mut CounterScope {}, "count\ncount\n", |runner| {
+        runner.with_context(40u32)
+    });
+
+    assert!(result.is_ok());
+    assert_eq!(output, "41\n42\n");
+}
+
+#[test]
+fn missing_context_should_not_stop_runner() {
+    let (result, output) = run(&mut CounterScope {}, "count\nshout\n");
+
+    assert!(result.is_ok());
+    assert_eq!(
+        output,
+        "No context of type u32 for this command\nUnknown command: shout\n"
+    );
+}
+
+struct InventoryScope {}
+
+#[cmdr]
+impl InventoryScope {
+    /// List all items
+    #[cmd]
+    fn list(&mut self, writer: &mut dyn LineWriter, _args: &[String]) -> CommandResult {
+        writer.write_line("Items:");
+        writer.write_records(&[
+            Record::new().field("name", "hammer").field("count", 3),
+            Record::new().field("name", "saw").field("count", 1),
+        ]);
+        Ok(Action::Done)
+    }
+}
+
+#[test]
+fn json_output_should_write_records_as_json_lines() {
+    let (result, output) = run_with(&mut InventoryScope {}, "list\n", |runner| {
+        runner.with_json_output(true)
+    });
+
+    assert!(result.is_ok());
+    assert_eq!(
+        output,
+        "Items:\n{\"name\":\"hammer\",\"count\":3}\n{\"name\":\"saw\",\"count\":1}\n"
+    );
+}
+
+struct DbCommands {
+    queries: u32,
+}
+
+#[cmdr(no_help)]
+impl DbCommands {
+    /// Run a query
+    #[cmd]
+    fn query(&mut self, writer: &mut dyn LineWriter, args: &[String]) -> CommandResult {
+        self.queries += 1;
+        writer.write_line(&format!("Query {}: {}", self.queries, args.join(" ")));
+        Ok(Action::Done)
+    }
+}
+
+struct MountingScope {
+    db: DbCommands,
+}
+
+#[cmdr(mount(db))]
+impl MountingScope {}
+
+#[test]
+fn mounted_commands_should_run_with_name_prefix() {
+    let mut scope = MountingScope {
+        db: DbCommands { queries: 0 },
+    };
+    let (result, output) = run(&mut scope, "db query users\ndb query jobs\ndb drop\n");
+
+    assert!(result.is_ok());
+    assert_eq!(
+        output,
+        "Query 1: users\nQuery 2: jobs\nUnknown command: db drop\n"
+    );
+}
+
+#[cfg(feature = "async")]
+mod async_mounts {
+    use super::*;
+    use cmdr::async_line_reader::BlockingLineReader;
+
+    struct AsyncDbCommands {}
+
+    #[cmdr(no_help)]
+    impl AsyncDbCommands {
+        /// Run a query
+        #[cmd]
+        async fn query(&mut self, writer: &mut dyn LineWriter, args: &[String]) -> CommandResult {
+            writer.write_line(&format!("Query: {}", args.join(" ")));
+            Ok(Action::Done)
+        }
+    }
+
+    struct AsyncMountingScope {
+        db: AsyncDbCommands,
+    }
+
+    #[cmdr(mount(db))]
+    impl AsyncMountingScope {
+        /// Wait a moment
+        #[cmd]
+        async fn pause(&mut self, _args: &[String]) -> CommandResult {
+            Ok(Action::Done)
+        }
+    }
+
+    #[test]
+    fn async_mounted_commands_should_be_awaited() {
+        let output = Output::default();
+        let mut runner = AsyncRunner::new(
+            BlockingLineReader::new(FileLineReader::new("db query users\n".as_bytes())),
+            output.clone(),
+        );
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+
+        let result = runtime.block_on(runner.run(&mut AsyncMountingScope {
+            db: AsyncDbCommands {},
+        }));
+
+        assert!(result.is_ok());
+        assert_eq!(*output.0.borrow(), "Query: users\n");
+    }
+
+    /// Writes the name of every command after it ran
+    #[derive(Debug)]
+    struct Trace;
+
+    impl Middleware for Trace {
+        fn handle<'a>(
+            &'a mut self,
+            _line: &'a Line,
+            command: &'a ScopeCmdDescription,
+            mut next: Next<'a>,
+        ) -> LocalBoxFuture<'a, CommandResult> {
+            Box::pin(async move {
+                let result = next.run().await;
+                next.writer().write_line(&format!("ran {}", command.name()));
+                result
+            })
+        }
+    }
+
+    #[test]
+    fn async_runner_should_run_middleware_around_async_commands() {
+        let output = Output::default();
+        let mut runner = AsyncRunner::new(
+            BlockingLineReader::new(FileLineReader::new("db query users\n".as_bytes())),
+            output.clone(),
+        )
+        .with_middleware(Trace);
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+
+        let result = runtime.block_on(runner.run(&mut AsyncMountingScope {
+            db: AsyncDbCommands {},
+        }));
+
+        assert!(result.is_ok());
+        assert_eq!(*output.0.borrow(), "Query: users\nran db\n");
+    }
+
+    #[test]
+    fn async_runner_should_return_exit_code() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let mut runner = AsyncRunner::new(
+            BlockingLineReader::new(FileLineReader::new("fatal\n".as_bytes())),
+            Output::default(),
+        );
+
+        assert_eq!(runtime.block_on(runner.run_main(&mut ExitScope {})), 3);
+    }
+}
+
+#[test]
+fn dynamic_command_registered_while_running_should_run_on_next_line() {
+    let mut scope = DynamicScope::new();
+    let registry = scope.registry();
+    scope.add_command(
+        ScopeCmdDescription::new("learn".to_string(), vec![], None),
+        move |_, _| {
+            registry.add_command(
+                ScopeCmdDescription::new("greet".to_string(), vec![], None),
+                |_, interaction| {
+                    interaction.write_line("Hello");
+                    Ok(Action::Done)
+                },
+            );
+            Ok(Action::Done)
+        },
+    );
+
+    let (result, output) = run(&mut scope, "greet\nlearn\ngreet\n");
+
+    assert!(result.is_ok());
+    assert_eq!(output, "Unknown command: greet\nHello\n");
+}
+
+struct ExitScope {}
+
+#[cmdr]
+impl ExitScope {
+    /// Stop with an exit code
+    #[cmd]
+    fn fatal(&mut self, _args: &[String]) -> CommandResult {
+        Err(Error::Fatal(3))
+    }
+
+    /// Fail to read a file
+    #[cmd]
+    fn read(&mut self, _args: &[String]) -> CommandResult {
+        Err(Error::Io(std::io::Error::from(
+            std::io::ErrorKind::NotFound,
+        )))
+    }
+}
+
+fn run_main(input: &'static str) -> i32 {
+    Runner::new(FileLineReader::new(input.as_bytes()), Output::default())
+        .run_main(&mut ExitScope {})
+}
+
+#[test]
+fn run_main_should_return_exit_code() {
+    assert_eq!(run_main("help\n"), 0);
+    assert_eq!(run_main("fatal\n"), 3);
+    assert_eq!(run_main("read\n"), 1);
+}
+
+#[test]
+fn run_main_with_should_choose_exit_code_for_errors() {
+    let code = Runner::new(FileLineReader::new("read\n".as_bytes()), Output::default())
+        .run_main_with(&mut ExitScope {}, |error| match error {
+            Error::Io(_) => 74,
+            error => error.exit_code(),
+        });
+
+    assert_eq!(code, 74);
+}
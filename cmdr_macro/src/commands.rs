@@ -2,53 +2,132 @@ use itertools::Itertools;
 use proc_macro2::{Ident, Span, TokenStream};
 use quote::{quote, ToTokens};
 use syn::{
-    Attribute, AttributeArgs, ImplItem, ImplItemMethod, ItemImpl, Lit, Meta, MetaList,
-    MetaNameValue, NestedMeta, ReturnType, Type,
+    AngleBracketedGenericArguments, Attribute, AttributeArgs, FnArg, GenericArgument, ImplItem,
+    ImplItemMethod, ItemImpl, Lit, Meta, MetaList, MetaNameValue, NestedMeta, PatType, Path,
+    PathArguments, ReturnType, TraitBound, Type, TypeParamBound, TypePath, TypeReference,
+    TypeTraitObject,
 };
 
 pub(crate) fn format_commands(input: &ItemImpl, meta: &AttributeArgs) -> TokenStream {
-    let (help_text, help_command) = parse_cmdr_attributes(meta);
-    let doc_help_text = parse_help_text(&input.attrs);
+    let (help_text, command_methods, mounts) = parse_scope(input, meta);
+    let command_calls: Vec<_> = command_methods.iter().map(CmdAttributes::to_call).collect();
+    let mount_descriptions = mounts.iter().map(Mount::to_description_tokens);
 
-    let mut command_methods = parse_commands(&input);
+    let quoted_help = quote_string_option(&help_text);
 
-    if let Some(command) = help_command {
-        command_methods.insert(
-            0,
-            CmdAttributes {
-                command: command.clone(),
-                method: Ident::new("help", Span::call_site()),
-                alias: vec![],
-                help: None,
-                arguments: vec![CmdArgument::Args],
-            },
+    let as_async = if command_methods.iter().any(|command| command.is_async) {
+        quote!(
+            fn as_async(&mut self) -> Option<&mut dyn cmdr::AsyncScope> {
+                Some(self)
+            }
         )
-    }
-    let command_calls: Vec<_> = command_methods.iter().map(CmdAttributes::to_call).collect();
-
-    let quoted_help = quote_string_option(&help_text.or(doc_help_text));
+    } else {
+        quote!()
+    };
 
     quote!(
         fn commands(&self) -> ScopeDescription {
             ScopeDescription::new(
                 #quoted_help,
                 vec![#(#command_methods)*]
-            )
+            )#(#mount_descriptions)*
         }
 
-        fn run_command(&mut self, command: &ScopeCmdDescription, args: &[String], writer: &mut dyn LineWriter) -> CommandResult {
+        fn run_command(&mut self, command: &ScopeCmdDescription, args: &[String], interaction: &mut Interaction) -> CommandResult {
             match command.name() {
                 #(#command_calls)*
+                #(#mounts)*
                 _ => Err(Error::InvalidCommand(command.name().to_string()))
             }
         }
+
+        #as_async
     )
 }
 
-/// Parses the help text and help command from the cmdr attribute
-fn parse_cmdr_attributes(meta: &AttributeArgs) -> (Option<String>, Option<String>) {
+/// Format the body of an AsyncScope implementation, returns None when there are no async commands
+pub(crate) fn format_async_commands(input: &ItemImpl, meta: &AttributeArgs) -> Option<TokenStream> {
+    let (_, command_methods, mounts) = parse_scope(input, meta);
+
+    if !command_methods.iter().any(|command| command.is_async) {
+        return None;
+    }
+
+    let command_calls = command_methods
+        .iter()
+        .map(|command| command.to_call().to_async_tokens());
+    let mounts = mounts.iter().map(Mount::to_async_tokens);
+
+    Some(quote!(
+        fn run_command_async<'a>(
+            &'a mut self,
+            command: &'a ScopeCmdDescription,
+            args: &'a [String],
+            interaction: &'a mut Interaction<'_>,
+        ) -> cmdr::LocalBoxFuture<'a, CommandResult> {
+            Box::pin(async move {
+                match command.name() {
+                    #(#command_calls)*
+                    #(#mounts)*
+                    _ => Err(Error::InvalidCommand(command.name().to_string()))
+                }
+            })
+        }
+    ))
+}
+
+/// Parse the scope help text, all commands, including the generated help command, and the mounted
+/// scopes
+fn parse_scope(
+    input: &ItemImpl,
+    meta: &AttributeArgs,
+) -> (Option<String>, Vec<CmdAttributes>, Vec<Mount>) {
+    let (help_text, help_command, mounts) = parse_cmdr_attributes(meta);
+    let doc_help_text = parse_help_text(&input.attrs);
+
+    let mut command_methods = parse_commands(input);
+
+    if let Some(command) = help_command {
+        // Scopes that override help(args) keep their help, others get help with the interaction
+        let (method, arguments) = if has_method(input, "help") {
+            ("help", vec![CmdArgument::Args])
+        } else {
+            (
+                "help_with",
+                vec![CmdArgument::Interaction, CmdArgument::Args],
+            )
+        };
+
+        command_methods.insert(
+            0,
+            CmdAttributes {
+                command: command.clone(),
+                method: Ident::new(method, Span::call_site()),
+                alias: vec![],
+                help: None,
+                usage: None,
+                arguments,
+                is_async: false,
+            },
+        )
+    }
+
+    (help_text.or(doc_help_text), command_methods, mounts)
+}
+
+/// Check if the impl block has a method with name
+fn has_method(input: &ItemImpl, name: &str) -> bool {
+    input
+        .items
+        .iter()
+        .any(|item| matches!(item, ImplItem::Method(method) if method.sig.ident == name))
+}
+
+/// Parses the help text, help command and mounted scopes from the cmdr attribute
+fn parse_cmdr_attributes(meta: &AttributeArgs) -> (Option<String>, Option<String>, Vec<Mount>) {
     let mut help_text = None;
     let mut help_command = Some("help".to_string());
+    let mut mounts = Vec::new();
 
     for meta_item in meta {
         match meta_item {
@@ -64,16 +143,81 @@ fn parse_cmdr_attributes(meta: &AttributeArgs) -> (Option<String>, Option<String
                     help_command = Some(lit.value());
                 }
             }
-            NestedMeta::Meta(Meta::Path(path)) => {
-                if path.is_ident("nohelp") | path.is_ident("no_help") {
-                    help_command = None
-                }
+            NestedMeta::Meta(Meta::Path(path))
+                if path.is_ident("nohelp") | path.is_ident("no_help") =>
+            {
+                help_command = None
+            }
+            NestedMeta::Meta(Meta::List(MetaList { path, nested, .. }))
+                if path.is_ident("mount") =>
+            {
+                mounts.extend(nested.iter().map(parse_mount))
             }
             _ => (),
         }
     }
 
-    (help_text, help_command)
+    (help_text, help_command, mounts)
+}
+
+/// Parse a mounted scope, `name` mounts the field `name` and `name = "field"` mounts `field`
+fn parse_mount(meta: &NestedMeta) -> Mount {
+    match meta {
+        NestedMeta::Meta(Meta::Path(path)) if path.get_ident().is_some() => {
+            let field = path.get_ident().unwrap().clone();
+            Mount {
+                name: field.to_string(),
+                field,
+            }
+        }
+        NestedMeta::Meta(Meta::NameValue(MetaNameValue {
+            path,
+            lit: Lit::Str(lit),
+            ..
+        })) if path.get_ident().is_some() => Mount {
+            name: path.get_ident().unwrap().to_string(),
+            field: Ident::new(&lit.value(), lit.span()),
+        },
+        _ => panic!("Invalid mount, expected 'name' or 'name = \"field\"'"),
+    }
+}
+
+/// A scope in a field that is mounted, its commands are run as `name command`
+#[derive(Debug, PartialEq)]
+struct Mount {
+    name: String,
+    field: Ident,
+}
+
+impl Mount {
+    /// Add the commands of the mounted scope to the description
+    fn to_description_tokens(&self) -> TokenStream {
+        let name = &self.name;
+        let field = &self.field;
+
+        quote!(.with_mount(#name, self.#field.commands()))
+    }
+
+    /// Run a command of the mounted scope from run_command_async, async commands are awaited
+    fn to_async_tokens(&self) -> TokenStream {
+        let name = &self.name;
+        let field = &self.field;
+
+        quote!(
+            #name => cmdr::run_mounted_async(&mut self.#field, #name, args, interaction).await,
+        )
+    }
+}
+
+impl ToTokens for Mount {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let name = &self.name;
+        let field = &self.field;
+
+        tokens.extend(quote!(
+            #name => cmdr::run_mounted(&mut self.#field, #name, args, interaction),
+        ));
+    }
 }
 
 fn quote_string_option(value: &Option<String>) -> TokenStream {
@@ -111,22 +255,85 @@ fn parse_cmd_signature(method: &ImplItemMethod) -> Vec<CmdArgument> {
         }
     }
 
-    // Get method parameters and check that they are the right type
-    let ins: Vec<_> = method.sig.inputs.iter().collect();
-
     // Todo:
     //  - check if first argument is &self or &mut self
-    //  - parse writer argument, should be reference, mutable, dyn and type Writer
-    //  - parse arg slice
-    //  - allow different argument order
-
-    match ins.len() {
-        2 => vec![CmdArgument::Args],
-        3 => vec![CmdArgument::Writer, CmdArgument::Args],
-        _ => panic!(
+
+    let arguments: Vec<_> = method
+        .sig
+        .inputs
+        .iter()
+        .filter_map(|input| match input {
+            FnArg::Receiver(_) => None,
+            FnArg::Typed(PatType { ty, .. }) => Some(parse_cmd_argument(&method_ident, ty)),
+        })
+        .collect();
+
+    if !arguments.contains(&CmdArgument::Args) {
+        panic!(
             "Invalid signature for command {}, expected '&mut self, args &[String]'",
             method_ident
-        ),
+        );
+    }
+
+    arguments
+}
+
+/// Determine what should be passed for a single cmd method argument by looking at its type
+fn parse_cmd_argument(method_ident: &Ident, argument: &Type) -> CmdArgument {
+    if let Type::Reference(TypeReference { elem, .. }) = argument {
+        match elem.as_ref() {
+            Type::Slice(_) => return CmdArgument::Args,
+            Type::Path(TypePath { path, .. })
+                if path
+                    .segments
+                    .last()
+                    .map_or(false, |segment| segment.ident == "Interaction") =>
+            {
+                return CmdArgument::Interaction
+            }
+            Type::Path(TypePath { path, .. }) if is_context(path) => return CmdArgument::Context,
+            Type::TraitObject(TypeTraitObject { bounds, .. })
+                if bounds.iter().any(|bound| is_trait(bound, "LineWriter")) =>
+            {
+                return CmdArgument::Writer
+            }
+            _ => (),
+        }
+    }
+
+    panic!(
+        "Invalid argument type for command {}, expected 'args: &[String]', 'writer: &mut dyn LineWriter', 'interaction: &mut Interaction' or 'context: &mut Context<T>'",
+        method_ident
+    )
+}
+
+/// Check if a type is `Context<T>` or `cmdr::Context<T>` with a single type argument
+fn is_context(path: &Path) -> bool {
+    let segments: Vec<_> = path.segments.iter().collect();
+    let context = match segments.as_slice() {
+        [context] => context,
+        [krate, context] if krate.ident == "cmdr" => context,
+        _ => return false,
+    };
+
+    match &context.arguments {
+        PathArguments::AngleBracketed(AngleBracketedGenericArguments { args, .. })
+            if context.ident == "Context" =>
+        {
+            args.len() == 1 && matches!(args.first(), Some(GenericArgument::Type(_)))
+        }
+        _ => false,
+    }
+}
+
+/// Check if a trait bound refers to the trait with the specified name
+fn is_trait(bound: &TypeParamBound, name: &str) -> bool {
+    match bound {
+        TypeParamBound::Trait(TraitBound { path, .. }) => path
+            .segments
+            .last()
+            .map_or(false, |segment| segment.ident == name),
+        _ => false,
     }
 }
 
@@ -148,6 +355,7 @@ fn parse_cmd_attributes(item: &ImplItem) -> Option<CmdAttributes> {
             let mut help_text = parse_help_text(attributes);
             let mut command_name = method_ident.to_string();
             let mut aliasses = Vec::new();
+            let mut usage = None;
 
             // Parse cmd fields
             for meta in cmd_attributes {
@@ -170,6 +378,8 @@ fn parse_cmd_attributes(item: &ImplItem) -> Option<CmdAttributes> {
                                     command_name = lit.value();
                                 } else if path.is_ident("help") {
                                     help_text = Some(lit.value());
+                                } else if path.is_ident("usage") {
+                                    usage = Some(lit.value());
                                 }
                             }
                             NestedMeta::Meta(Meta::List(ref alias_list))
@@ -198,7 +408,9 @@ fn parse_cmd_attributes(item: &ImplItem) -> Option<CmdAttributes> {
                 method: method_ident,
                 alias: aliasses,
                 help: help_text,
+                usage,
                 arguments: parse_cmd_signature(method),
+                is_async: method.sig.asyncness.is_some(),
             })
         } else {
             // Method has no cmd attribute so is not a command
@@ -211,7 +423,7 @@ fn parse_cmd_attributes(item: &ImplItem) -> Option<CmdAttributes> {
 }
 
 /// Parse documentation from attributes
-fn parse_help_text(attrs: &Vec<Attribute>) -> Option<String> {
+fn parse_help_text(attrs: &[Attribute]) -> Option<String> {
     let mut help_lines = attrs
         .iter()
         .map(Attribute::parse_meta)
@@ -229,7 +441,7 @@ fn parse_help_text(attrs: &Vec<Attribute>) -> Option<String> {
 
 fn parse_doc_string(meta: Meta) -> Option<String> {
     if let Meta::NameValue(name_val) = meta {
-        if let syn::Lit::Str(string) = name_val.lit {
+        if let Lit::Str(string) = name_val.lit {
             Some(string.value().trim().to_owned())
         } else {
             None
@@ -246,17 +458,37 @@ struct CmdAttributes {
     method: Ident,
     alias: Vec<String>,
     help: Option<String>,
+    usage: Option<String>,
     arguments: Vec<CmdArgument>,
+    is_async: bool,
 }
 
 /// Single cmd method argument type
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 enum CmdArgument {
     /// Writer argument
     Writer,
 
     /// Arguments slice
     Args,
+
+    /// Interaction handle to ask the user questions
+    Interaction,
+
+    /// Context of the runner that is shared by all scopes
+    Context,
+}
+
+impl CmdArgument {
+    /// The run_command parameter that is passed for this argument
+    fn to_call_argument(&self) -> TokenStream {
+        match self {
+            CmdArgument::Writer => quote!(interaction),
+            CmdArgument::Args => quote!(args),
+            CmdArgument::Interaction => quote!(interaction),
+            CmdArgument::Context => quote!(context),
+        }
+    }
 }
 
 impl CmdAttributes {
@@ -264,6 +496,8 @@ impl CmdAttributes {
         CmdCall {
             command: self.command.clone(),
             method: self.method.clone(),
+            arguments: self.arguments.clone(),
+            is_async: self.is_async,
         }
     }
 }
@@ -278,13 +512,14 @@ impl ToTokens for CmdAttributes {
             .map(|alias| quote!(#alias.to_string()))
             .collect();
         let alias_quote = quote!(vec![#(#alias_list),*]);
+        let usage = self.usage.iter().map(|usage| quote!(.with_usage(#usage)));
 
         tokens.extend(quote!(
             ScopeCmdDescription::new(
                 #command.to_string(),
                 #alias_quote,
                 #help_text,
-            ),
+            )#(#usage)*,
         ))
     }
 }
@@ -294,16 +529,62 @@ impl ToTokens for CmdAttributes {
 struct CmdCall {
     command: String,
     method: Ident,
+    arguments: Vec<CmdArgument>,
+    is_async: bool,
+}
+
+impl CmdCall {
+    /// Generate the match arm that calls the command from run_command_async
+    fn to_async_tokens(&self) -> TokenStream {
+        let command = &self.command;
+        let call = self.call_tokens(self.is_async);
+
+        quote!(#command => #call,)
+    }
+
+    /// Generate the method call, awaited for async commands. The context is taken out of the
+    /// interaction while the command runs so both can be passed to it
+    fn call_tokens(&self, awaited: bool) -> TokenStream {
+        let method = &self.method;
+        let arguments = self.arguments.iter().map(CmdArgument::to_call_argument);
+
+        let call = if awaited {
+            quote!(self.#method(#(#arguments),*).await)
+        } else {
+            quote!(self.#method(#(#arguments),*))
+        };
+
+        if self.arguments.contains(&CmdArgument::Context) {
+            quote!({
+                let mut context = interaction.take_context();
+                let result = match cmdr::Context::downcast(&mut context) {
+                    Ok(context) => #call,
+                    Err(error) => Err(error),
+                };
+                interaction.set_context(context);
+                result
+            })
+        } else {
+            call
+        }
+    }
 }
 
 impl ToTokens for CmdCall {
     fn to_tokens(&self, tokens: &mut TokenStream) {
         let command = &self.command;
-        let method = &self.method;
 
-        tokens.extend(quote!(
-            #command => self.#method(args),
-        ));
+        if self.is_async {
+            // Async commands can not be called from the blocking run_command
+            tokens.extend(quote!(
+                #command => Err(Error::AsyncCommand(#command.to_string())),
+            ));
+        } else {
+            let call = self.call_tokens(false);
+            tokens.extend(quote!(
+                #command => #call,
+            ));
+        }
     }
 }
 
@@ -516,4 +797,176 @@ mod when_parsing_function_cmd_attributes {
 
         assert_eq!(parsed.alias, vec!["one", "two", "three"]);
     }
+
+    #[test]
+    fn should_parse_usage_from_cmd_attribute() {
+        let parsed = parse_cmd_attributes(
+            &parse_str(
+                r###"
+                #[cmd(usage="<name> [greeting]")]
+                fn method(&self, args: &[String]) {}
+                "###,
+            )
+            .unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(parsed.usage, Some("<name> [greeting]".to_string()));
+    }
+
+    #[test]
+    fn should_parse_args_argument() {
+        let parsed = parse_cmd_attributes(
+            &parse_str(
+                r###"
+                #[cmd]
+                fn method(&self, args: &[String]) {}
+                "###,
+            )
+            .unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(parsed.arguments, vec![CmdArgument::Args]);
+    }
+
+    #[test]
+    fn should_parse_writer_argument_in_any_position() {
+        let parsed = parse_cmd_attributes(
+            &parse_str(
+                r###"
+                #[cmd]
+                fn method(&self, args: &[String], writer: &mut dyn LineWriter) {}
+                "###,
+            )
+            .unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            parsed.arguments,
+            vec![CmdArgument::Args, CmdArgument::Writer]
+        );
+    }
+
+    #[test]
+    fn should_parse_interaction_argument() {
+        let parsed = parse_cmd_attributes(
+            &parse_str(
+                r###"
+                #[cmd]
+                fn method(&self, interaction: &mut Interaction, args: &[String]) {}
+                "###,
+            )
+            .unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            parsed.arguments,
+            vec![CmdArgument::Interaction, CmdArgument::Args]
+        );
+    }
+
+    #[test]
+    fn should_parse_context_argument() {
+        let parsed = parse_cmd_attributes(
+            &parse_str(
+                r###"
+                #[cmd]
+                fn method(&self, context: &mut Context<State>, args: &[String]) {}
+                "###,
+            )
+            .unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            parsed.arguments,
+            vec![CmdArgument::Context, CmdArgument::Args]
+        );
+    }
+
+    #[test]
+    fn should_parse_qualified_context_argument() {
+        let parsed = parse_cmd_attributes(
+            &parse_str(
+                r###"
+                #[cmd]
+                fn method(&self, context: &mut cmdr::Context<State>, args: &[String]) {}
+                "###,
+            )
+            .unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            parsed.arguments,
+            vec![CmdArgument::Context, CmdArgument::Args]
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn should_not_parse_other_context_types() {
+        parse_cmd_attributes(
+            &parse_str(
+                r###"
+                #[cmd]
+                fn method(&self, context: &mut tera::Context, args: &[String]) {}
+                "###,
+            )
+            .unwrap(),
+        );
+    }
+
+    #[test]
+    fn should_parse_mounts() {
+        let meta: Vec<NestedMeta> = vec![parse_str(r#"mount(db, files = "fs")"#).unwrap()];
+        let (_, _, mounts) = parse_cmdr_attributes(&meta);
+
+        assert_eq!(
+            mounts,
+            vec![
+                Mount {
+                    name: "db".to_string(),
+                    field: Ident::new("db", Span::call_site())
+                },
+                Mount {
+                    name: "files".to_string(),
+                    field: Ident::new("fs", Span::call_site())
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn should_parse_async_command() {
+        let parsed = parse_cmd_attributes(
+            &parse_str(
+                r###"
+                #[cmd]
+                async fn method(&self, args: &[String]) {}
+                "###,
+            )
+            .unwrap(),
+        )
+        .unwrap();
+
+        assert!(parsed.is_async);
+    }
+
+    #[test]
+    #[should_panic]
+    fn should_panic_on_unknown_argument_type() {
+        parse_cmd_attributes(
+            &parse_str(
+                r###"
+                #[cmd]
+                fn method(&self, args: &[String], number: i64) {}
+                "###,
+            )
+            .unwrap(),
+        );
+    }
 }
@@ -24,7 +24,7 @@ mod commands;
 mod overrides;
 mod parsing;
 
-use crate::commands::format_commands;
+use crate::commands::{format_async_commands, format_commands};
 use crate::overrides::format_overrides;
 use crate::parsing::parse_self_type;
 use proc_macro::TokenStream;
@@ -49,6 +49,13 @@ pub fn cmdr(meta_stream: TokenStream, code_stream: TokenStream) -> TokenStream {
 
     let commands = format_commands(&input, &meta);
     let overrides = format_overrides(&input, &self_type);
+    let async_commands = format_async_commands(&input, &meta).map(|async_commands| {
+        quote!(
+            impl#self_generics cmdr::AsyncScope for #self_type #self_where {
+                #async_commands
+            }
+        )
+    });
 
     TokenStream::from(quote!(
         #input
@@ -57,6 +64,8 @@ pub fn cmdr(meta_stream: TokenStream, code_stream: TokenStream) -> TokenStream {
             #commands
             #overrides
         }
+
+        #async_commands
     ))
 }
 
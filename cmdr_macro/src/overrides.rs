@@ -14,7 +14,7 @@ pub(crate) fn format_overrides(input: &ItemImpl, self_type: &TypePath) -> TokenS
         if let ImplItem::Method(method) = item {
             overrides.extend(match method.sig.ident.to_string().as_ref() {
                 "prompt" => {
-                    check_signature(&method, "fn prompt(&self) -> String {}");
+                    check_signature(method, "fn prompt(&self) -> String {}");
 
                     quote!(
                         fn prompt(&self) -> String {
@@ -23,7 +23,7 @@ pub(crate) fn format_overrides(input: &ItemImpl, self_type: &TypePath) -> TokenS
                     )
                 }
                 "help" => {
-                    check_signature(&method, "fn help(&self, args: &[String]) -> CommandResult {}");
+                    check_signature(method, "fn help(&self, args: &[String]) -> CommandResult {}");
 
                     quote!(
                         fn help(&self, args: &[String]) -> CommandResult {
@@ -31,8 +31,26 @@ pub(crate) fn format_overrides(input: &ItemImpl, self_type: &TypePath) -> TokenS
                         }
                     )
                 }
+                "help_with" => {
+                    check_signature(method, "fn help_with(&self, interaction: &mut Interaction, args: &[String]) -> CommandResult {}");
+
+                    quote!(
+                        fn help_with(&self, interaction: &mut Interaction, args: &[String]) -> CommandResult {
+                            #self_type::help_with(&self, interaction, args)
+                        }
+                    )
+                }
+                "sub_scopes" => {
+                    check_signature(method, "fn sub_scopes(&self) -> Vec<(String, ScopeDescription)> {}");
+
+                    quote!(
+                        fn sub_scopes(&self) -> Vec<(String, ScopeDescription)> {
+                            #self_type::sub_scopes(&self)
+                        }
+                    )
+                }
                 "handle_error" => {
-                    check_signature(&method, "fn handle_error(&mut self, error: Error) -> CommandResult {}");
+                    check_signature(method, "fn handle_error(&mut self, error: Error) -> CommandResult {}");
 
                     quote!(
                         fn handle_error(&mut self, error: Error) -> CommandResult {
@@ -41,7 +59,7 @@ pub(crate) fn format_overrides(input: &ItemImpl, self_type: &TypePath) -> TokenS
                     )
                 },
                 "default" => {
-                    check_signature(&method, "fn default(&mut self, command: &Line) -> CommandResult {}");
+                    check_signature(method, "fn default(&mut self, command: &Line) -> CommandResult {}");
 
                     quote!(
                         fn default(&mut self, command: &Line) -> CommandResult {
@@ -50,7 +68,7 @@ pub(crate) fn format_overrides(input: &ItemImpl, self_type: &TypePath) -> TokenS
                     )
                 },
                 "before_loop" => {
-                    check_signature(&method, "fn before_loop(&mut self) {}");
+                    check_signature(method, "fn before_loop(&mut self) {}");
 
                     quote!(
                         fn before_loop(&mut self) {
@@ -59,7 +77,7 @@ pub(crate) fn format_overrides(input: &ItemImpl, self_type: &TypePath) -> TokenS
                     )
                 }
                 "before_command" => {
-                    check_signature(&method, "fn before_command(&mut self, line: Line) -> Line {}");
+                    check_signature(method, "fn before_command(&mut self, line: Line) -> Line {}");
 
                     quote!(
                         fn before_command(&mut self, line: Line) -> Line {
@@ -68,7 +86,7 @@ pub(crate) fn format_overrides(input: &ItemImpl, self_type: &TypePath) -> TokenS
                     )
                 }
                 "after_command" => {
-                    check_signature(&method, "fn after_command(&mut self, line: &Line, result: CommandResult) -> CommandResult {}");
+                    check_signature(method, "fn after_command(&mut self, line: &Line, result: CommandResult) -> CommandResult {}");
 
                     quote!(
                         fn after_command(&mut self, line: &Line, result: CommandResult) -> CommandResult {
@@ -77,7 +95,7 @@ pub(crate) fn format_overrides(input: &ItemImpl, self_type: &TypePath) -> TokenS
                     )
                 }
                 "after_loop" => { 
-                    check_signature(&method, "fn after_loop(&mut self) {}");
+                    check_signature(method, "fn after_loop(&mut self) {}");
 
                     quote!(
                         fn after_loop(&mut self) {
@@ -150,6 +168,20 @@ mod tests {
         );
     }
 
+    #[test]
+    fn should_override_help_with_when_available() {
+        let source = syn::parse_str(
+            "impl SomeImpl {fn help_with(&self, interaction: &mut Interaction, args: &[String]) -> CommandResult { }}",
+        )
+        .unwrap();
+        let self_type = parse_self_type(&source).unwrap();
+
+        tokens_eq(
+            format_overrides(&source, &self_type),
+            "fn help_with(&self, interaction: &mut Interaction, args: &[String]) -> CommandResult { SomeImpl::help_with(&self, interaction, args) }",
+        );
+    }
+
     #[test]
     fn should_override_handle_error_when_available() {
         let source = syn::parse_str(
@@ -178,6 +210,20 @@ mod tests {
         );
     }
 
+    #[test]
+    fn should_override_sub_scopes_when_available() {
+        let source = syn::parse_str(
+            "impl SomeImpl {fn sub_scopes(&self) -> Vec<(String, ScopeDescription)> { }}",
+        )
+        .unwrap();
+        let self_type = parse_self_type(&source).unwrap();
+
+        tokens_eq(
+            format_overrides(&source, &self_type),
+            "fn sub_scopes(&self) -> Vec<(String, ScopeDescription)> { SomeImpl::sub_scopes(&self) }",
+        );
+    }
+
     #[test]
     fn should_override_before_loop_when_available() {
         let source = syn::parse_str("impl SomeImpl {fn before_loop(&mut self) { }}").unwrap();